@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Bytes hashed from each end of the file.
+const CHUNK: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Known {
+    mtime: u64,
+    len: u64,
+    fingerprint: String,
+}
+
+/// Remembers the fingerprint of every path seen, so unchanged files are only read once.
+#[derive(Debug, Default)]
+struct FingerprintIndex {
+    map: HashMap<PathBuf, Known>,
+    dirty: bool,
+}
+
+fn index_path() -> PathBuf {
+    super::cache_dir().join("fingerprints.json")
+}
+
+fn index() -> &'static Mutex<FingerprintIndex> {
+    static INDEX: OnceLock<Mutex<FingerprintIndex>> = OnceLock::new();
+
+    INDEX.get_or_init(|| {
+        let map = crate::config::read_json_or_default(&index_path());
+        Mutex::new(FingerprintIndex { map, dirty: false })
+    })
+}
+
+/// A fast content fingerprint: the file size plus the first and last 64 KiB. Renamed or
+/// moved files keep their fingerprint, so their thumbnails stay valid.
+pub fn fingerprint(path: &Path, mtime: u64, len: u64) -> Option<String> {
+    if let Ok(idx) = index().lock()
+        && let Some(k) = idx.map.get(path)
+        && k.mtime == mtime
+        && k.len == len
+    {
+        return Some(k.fingerprint.clone());
+    }
+
+    let fingerprint = compute(path, len).ok()?;
+
+    if let Ok(mut idx) = index().lock() {
+        idx.map.insert(
+            path.to_path_buf(),
+            Known {
+                mtime,
+                len,
+                fingerprint: fingerprint.clone(),
+            },
+        );
+        idx.dirty = true;
+    }
+
+    Some(fingerprint)
+}
+
+fn compute(path: &Path, len: u64) -> io::Result<String> {
+    let mut f = File::open(path)?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&len.to_le_bytes());
+
+    let mut buf = vec![0u8; CHUNK.min(len) as usize];
+    f.read_exact(&mut buf)?;
+    hasher.update(&buf);
+
+    if len > CHUNK {
+        let tail = CHUNK.min(len - CHUNK);
+        buf.resize(tail as usize, 0);
+        f.seek(SeekFrom::Start(len - tail))?;
+        f.read_exact(&mut buf)?;
+        hasher.update(&buf);
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Drops entries for files that no longer exist.
+pub fn forget_missing() {
+    if let Ok(mut idx) = index().lock() {
+        let before = idx.map.len();
+        idx.map.retain(|p, _| p.exists());
+        if idx.map.len() != before {
+            idx.dirty = true;
+        }
+    }
+}
+
+pub fn save() -> io::Result<()> {
+    let Ok(mut idx) = index().lock() else {
+        return Ok(());
+    };
+    if !idx.dirty {
+        return Ok(());
+    }
+
+    crate::config::write_json_atomic(&index_path(), &idx.map)?;
+    idx.dirty = false;
+    Ok(())
+}
@@ -1,42 +1,240 @@
+mod fingerprint;
+mod pack;
+
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
-use std::time::UNIX_EPOCH;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::collections::HashSet;
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{CacheFormat, CacheKeying};
+use crate::image::ThumbSpec;
+use pack::Pack;
 
 pub fn cache_dir() -> PathBuf {
     crate::config::config_dir().join("cache")
 }
 
-pub fn cached_thumb_path(img_path: &Path, thumb_size: u32) -> Option<PathBuf> {
-    let meta = fs::metadata(img_path).ok();
-
-    let (mtime, len) = if let Some(m) = meta {
-        let len = m.len();
-        let mt = m
-            .modified()
-            .ok()
-            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-            .map(|d| d.as_secs())
-            .unwrap_or(0);
-        (mt, len)
-    } else {
-        (0, 0)
+struct CacheSettings {
+    format: CacheFormat,
+    keying: CacheKeying,
+}
+
+fn settings() -> &'static CacheSettings {
+    static SETTINGS: OnceLock<CacheSettings> = OnceLock::new();
+
+    SETTINGS.get_or_init(|| {
+        let cfg = crate::config::load_config();
+        CacheSettings {
+            format: cfg.cache_format,
+            keying: cfg.cache_keying,
+        }
+    })
+}
+
+enum Store {
+    Files,
+    Pack(Mutex<Pack>),
+}
+
+/// The storage backend picked by `cache_format`, opened on first use. Switching to the pack
+/// format migrates any thumbnails left in the per-file layout.
+fn store() -> &'static Store {
+    static STORE: OnceLock<Store> = OnceLock::new();
+
+    STORE.get_or_init(|| match settings().format {
+        CacheFormat::Files => Store::Files,
+        CacheFormat::Pack => match Pack::open(&cache_dir()) {
+            Ok(mut pack) => {
+                let _ = migrate_files_to_pack(&mut pack);
+                Store::Pack(Mutex::new(pack))
+            }
+            Err(e) => {
+                eprintln!("Failed to open thumbnail pack, using per-file cache: {e}");
+                Store::Files
+            }
+        },
+    })
+}
+
+fn with_pack<T>(f: impl FnOnce(&mut Pack) -> T) -> Option<T> {
+    match store() {
+        Store::Files => None,
+        Store::Pack(m) => m.lock().ok().map(|mut p| f(&mut p)),
+    }
+}
+
+/// `(mtime, len)` of a source image, or zeros if it can't be read.
+fn file_stamp(img_path: &Path) -> (u64, u64) {
+    match fs::metadata(img_path) {
+        Ok(m) => {
+            let mt = m
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            (mt, m.len())
+        }
+        Err(_) => (0, 0),
+    }
+}
+
+pub fn thumb_key(img_path: &Path, spec: ThumbSpec) -> String {
+    let (mtime, len) = file_stamp(img_path);
+
+    let content = match settings().keying {
+        CacheKeying::Path => None,
+        CacheKeying::Content => fingerprint::fingerprint(img_path, mtime, len),
+    };
+
+    let key = match content {
+        Some(fp) => format!("content|{}|{}{}", fp, spec.size, spec.cache_tag()),
+        None => format!(
+            "{}|{}|{}|{}{}",
+            img_path.to_string_lossy(),
+            mtime,
+            len,
+            spec.size,
+            spec.cache_tag()
+        ),
+    };
+    blake3::hash(key.as_bytes()).to_hex().to_string()
+}
+
+fn composites_dir() -> PathBuf {
+    cache_dir().join("composites")
+}
+
+/// Where the screen-sized render of `img_path` described by `tag` is kept. Only the current
+/// wallpaper's render is needed, so writing one (see [`write_composite`]) drops the others.
+pub fn composite_path(img_path: &Path, tag: &str) -> PathBuf {
+    let (mtime, len) = file_stamp(img_path);
+    let key = format!("{}|{}|{}|{}", img_path.to_string_lossy(), mtime, len, tag);
+    composites_dir().join(format!("{}.png", blake3::hash(key.as_bytes()).to_hex()))
+}
+
+pub fn write_composite(target: &Path, img: &RgbaImage) -> io::Result<()> {
+    let dir = composites_dir();
+    fs::create_dir_all(&dir)?;
+
+    let tmp = target.with_extension("png.tmp");
+    img.save_with_format(&tmp, image::ImageFormat::Png)
+        .map_err(io::Error::other)?;
+    fs::rename(&tmp, target)?;
+
+    for ent in fs::read_dir(&dir)?.flatten() {
+        if ent.path() != target {
+            let _ = fs::remove_file(ent.path());
+        }
+    }
+    Ok(())
+}
+
+pub fn cached_thumb_path(img_path: &Path, spec: ThumbSpec) -> Option<PathBuf> {
+    Some(cache_dir().join(format!("{}.png", thumb_key(img_path, spec))))
+}
+
+pub fn has_thumb(img_path: &Path, spec: ThumbSpec) -> bool {
+    match store() {
+        Store::Files => cached_thumb_path(img_path, spec).is_some_and(|p| p.exists()),
+        Store::Pack(_) => {
+            let key = thumb_key(img_path, spec);
+            with_pack(|p| p.contains(&key)).unwrap_or(false)
+        }
+    }
+}
+
+/// Reads a cached thumbnail, counting the lookup towards the hit rate.
+pub fn read_thumb(img_path: &Path, spec: ThumbSpec) -> Option<RgbaImage> {
+    let img = match store() {
+        Store::Files => cached_thumb_path(img_path, spec).and_then(|path| {
+            let img = image::open(&path).ok()?.to_rgba8();
+            touch(&path);
+            Some(img)
+        }),
+        Store::Pack(_) => {
+            let key = thumb_key(img_path, spec);
+            with_pack(|p| p.get(&key)).flatten().and_then(|data| {
+                let (header, raw) = qoi::decode_to_vec(data).ok()?;
+                RgbaImage::from_raw(header.width, header.height, raw)
+            })
+        }
+    };
+
+    match img {
+        Some(_) => HITS.fetch_add(1, Ordering::Relaxed),
+        None => MISSES.fetch_add(1, Ordering::Relaxed),
     };
+    img
+}
+
+pub fn write_thumb(img_path: &Path, spec: ThumbSpec, thumb: &RgbaImage) -> io::Result<()> {
+    match store() {
+        Store::Files => {
+            let Some(cache_path) = cached_thumb_path(img_path, spec) else {
+                return Ok(());
+            };
+            fs::create_dir_all(cache_dir())?;
+
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0);
+            let tmp_path = cache_path.with_extension(format!("{}.png", nanos));
+
+            thumb.save(&tmp_path).map_err(io::Error::other)?;
+            fs::rename(&tmp_path, &cache_path)
+        }
+        Store::Pack(_) => {
+            let key = thumb_key(img_path, spec);
+            let data = qoi::encode_to_vec(thumb.as_raw(), thumb.width(), thumb.height())
+                .map_err(io::Error::other)?;
+            with_pack(|p| p.insert(&key, &data)).unwrap_or_else(|| Err(io::Error::other("thumbnail pack unavailable")))
+        }
+    }
+}
 
-    let key = format!("{}|{}|{}|{}", img_path.to_string_lossy(), mtime, len, thumb_size);
-    let hash = blake3::hash(key.as_bytes()).to_hex().to_string();
+fn migrate_files_to_pack(pack: &mut Pack) -> io::Result<usize> {
+    let mut migrated = 0usize;
+    for (path, _, _) in thumbnail_files() {
+        let Some(key) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else {
+            continue;
+        };
 
-    Some(cache_dir().join(format!("{}.png", hash)))
+        if !pack.contains(&key)
+            && let Ok(img) = image::open(&path)
+        {
+            let rgba = img.to_rgba8();
+            let data = qoi::encode_to_vec(rgba.as_raw(), rgba.width(), rgba.height())
+                .map_err(io::Error::other)?;
+            pack.insert(&key, &data)?;
+            migrated += 1;
+        }
+        let _ = fs::remove_file(&path);
+    }
+    Ok(migrated)
 }
 
-pub fn clean_orphan_thumbnails_for_images(images: &[PathBuf], thumb_size: u32) -> std::io::Result<usize> {
-    let mut expected: HashSet<PathBuf> = HashSet::new();
+pub fn clean_orphan_thumbnails_for_images(images: &[PathBuf], specs: &[ThumbSpec]) -> std::io::Result<usize> {
+    let mut expected: HashSet<String> = HashSet::new();
     for img in images.iter() {
-        if let Some(p) = cached_thumb_path(img, thumb_size) {
-            expected.insert(p);
+        for spec in specs {
+            expected.insert(thumb_key(img, *spec));
         }
     }
 
+    fingerprint::forget_missing();
+    let _ = fingerprint::save();
+
+    if let Some(res) = with_pack(|p| p.remove_where(|k, _| !expected.contains(k))) {
+        return res.map(|(removed, _)| removed);
+    }
+
     let cdir = cache_dir();
     let _ = fs::create_dir_all(&cdir);
     let mut removed = 0usize;
@@ -47,13 +245,178 @@ pub fn clean_orphan_thumbnails_for_images(images: &[PathBuf], thumb_size: u32) -
             let is_png = path.extension().and_then(|e| e.to_str()).map(|s| s.eq_ignore_ascii_case("png")).unwrap_or(false);
             if !is_png { continue; }
 
-            if !expected.contains(&path) {
-                if fs::remove_file(&path).is_ok() {
-                    removed += 1;
-                }
+            let key = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+            if !expected.contains(key) && fs::remove_file(&path).is_ok() {
+                removed += 1;
             }
         }
     }
 
     Ok(removed)
 }
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Lifetime hit/miss counters, persisted next to the thumbnails.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f64 / total as f64)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct CacheUsage {
+    pub entries: usize,
+    pub bytes: u64,
+    pub oldest_access: Option<SystemTime>,
+    pub newest_access: Option<SystemTime>,
+    /// Size of the pack file including dead records, when the pack format is in use.
+    pub pack_bytes: Option<u64>,
+}
+
+fn stats_path() -> PathBuf {
+    cache_dir().join("stats.json")
+}
+
+/// Marks a thumbnail file as used. The file's mtime doubles as its last-access time for LRU
+/// eviction, since atime is often disabled (noatime/relatime).
+fn touch(thumb: &Path) {
+    if let Ok(f) = fs::File::options().write(true).open(thumb) {
+        let _ = f.set_modified(SystemTime::now());
+    }
+}
+
+pub fn load_stats() -> CacheStats {
+    crate::config::read_json_or_default(&stats_path())
+}
+
+/// Adds the counters gathered by this process to the persisted totals, and writes back the
+/// pack and fingerprint indexes so access times and fingerprints survive.
+pub fn flush_stats() -> std::io::Result<()> {
+    with_pack(|p| p.save_index()).transpose()?;
+    fingerprint::save()?;
+
+    let hits = HITS.swap(0, Ordering::Relaxed);
+    let misses = MISSES.swap(0, Ordering::Relaxed);
+    if hits == 0 && misses == 0 {
+        return Ok(());
+    }
+
+    let mut stats = load_stats();
+    stats.hits += hits;
+    stats.misses += misses;
+
+    crate::config::write_json_atomic(&stats_path(), &stats)
+}
+
+fn thumbnail_files() -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(read_dir) = fs::read_dir(cache_dir()) else {
+        return Vec::new();
+    };
+
+    read_dir
+        .flatten()
+        .filter_map(|ent| {
+            let path = ent.path();
+            let is_png = path.extension().and_then(|e| e.to_str()).map(|s| s.eq_ignore_ascii_case("png")).unwrap_or(false);
+            if !is_png {
+                return None;
+            }
+            let meta = ent.metadata().ok()?;
+            let accessed = meta.modified().unwrap_or(UNIX_EPOCH);
+            Some((path, meta.len(), accessed))
+        })
+        .collect()
+}
+
+/// `(key, size, last access)` for every cached thumbnail.
+fn thumbnail_entries() -> Vec<(String, u64, SystemTime)> {
+    if let Some(entries) = with_pack(|p| {
+        p.entries()
+            .map(|(k, e)| (k.clone(), e.len, UNIX_EPOCH + Duration::from_secs(e.accessed)))
+            .collect::<Vec<_>>()
+    }) {
+        return entries;
+    }
+
+    thumbnail_files()
+        .into_iter()
+        .filter_map(|(path, len, accessed)| {
+            let key = path.file_stem()?.to_str()?.to_string();
+            Some((key, len, accessed))
+        })
+        .collect()
+}
+
+pub fn usage() -> CacheUsage {
+    let entries = thumbnail_entries();
+
+    CacheUsage {
+        entries: entries.len(),
+        bytes: entries.iter().map(|(_, len, _)| len).sum(),
+        oldest_access: entries.iter().map(|(_, _, t)| *t).min(),
+        newest_access: entries.iter().map(|(_, _, t)| *t).max(),
+        pack_bytes: with_pack(|p| p.file_len()),
+    }
+}
+
+/// Evicts least-recently-used thumbnails until the cache fits both limits. A limit of 0
+/// means unlimited. Returns the number of files removed and the bytes freed.
+pub fn prune_to_budget(max_bytes: u64, max_entries: usize) -> (usize, u64) {
+    let mut entries = thumbnail_entries();
+    let mut total: u64 = entries.iter().map(|(_, len, _)| len).sum();
+    let mut count = entries.len();
+
+    let over = |total: u64, count: usize| {
+        (max_bytes > 0 && total > max_bytes) || (max_entries > 0 && count > max_entries)
+    };
+
+    if !over(total, count) {
+        return (0, 0);
+    }
+
+    entries.sort_by_key(|(_, _, accessed)| *accessed);
+
+    let mut evict: HashSet<String> = HashSet::new();
+    for (key, len, _) in entries {
+        if !over(total, count) {
+            break;
+        }
+        total -= len;
+        count -= 1;
+        evict.insert(key);
+    }
+
+    if let Some(res) = with_pack(|p| p.remove_where(|k, _| evict.contains(k))) {
+        return res.unwrap_or((0, 0));
+    }
+
+    let mut removed = 0usize;
+    let mut freed = 0u64;
+    for key in evict {
+        let path = cache_dir().join(format!("{key}.png"));
+        let len = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        if fs::remove_file(&path).is_ok() {
+            removed += 1;
+            freed += len;
+        }
+    }
+
+    (removed, freed)
+}
+
+pub async fn prune_cache(max_mb: u64, max_entries: usize) -> (usize, u64) {
+    let max_bytes = max_mb.saturating_mul(1024 * 1024);
+
+    tokio::task::spawn_blocking(move || prune_to_budget(max_bytes, max_entries))
+        .await
+        .unwrap_or((0, 0))
+}
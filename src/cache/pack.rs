@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{FileExt, MetadataExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Packed thumbnail store: every thumbnail is appended to one `thumbs.pack` file as QOI and
+/// located through `thumbs.idx`, a text index with one `key offset len accessed` line per
+/// entry. New entries are appended to the index immediately; access times are only written
+/// back when the index is saved, and removals rewrite the index and compact the pack.
+///
+/// Several processes may use the same pack (e.g. `--generate` while the picker is open), so
+/// every write happens under an advisory lock on `thumbs.lock` and first catches up with what
+/// the others wrote: see [`Pack::refresh`].
+#[derive(Debug)]
+pub struct Pack {
+    dir: PathBuf,
+    file: File,
+    len: u64,
+    index: HashMap<String, PackEntry>,
+    idx_pos: IndexPos,
+    dirty: bool,
+}
+
+/// How much of `thumbs.idx` has been read, so a refresh only parses what was appended since.
+/// A different inode means the index was rewritten and must be read again in full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct IndexPos {
+    ino: u64,
+    read: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PackEntry {
+    pub offset: u64,
+    pub len: u64,
+    pub accessed: u64,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn index_line(key: &str, e: &PackEntry) -> String {
+    format!("{} {} {} {}\n", key, e.offset, e.len, e.accessed)
+}
+
+/// Reads `thumbs.idx` from byte `from` on, keeping only records that lie within a pack of
+/// `pack_len` bytes. A trailing line without its newline is left for the next read.
+fn read_index(dir: &Path, pack_len: u64, from: u64) -> (HashMap<String, PackEntry>, IndexPos) {
+    let mut index = HashMap::new();
+    let Ok(mut f) = File::open(dir.join("thumbs.idx")) else {
+        return (index, IndexPos::default());
+    };
+    let ino = f.metadata().map(|m| m.ino()).unwrap_or(0);
+    let mut buf = Vec::new();
+    if f.seek(SeekFrom::Start(from)).is_err() || f.read_to_end(&mut buf).is_err() {
+        return (index, IndexPos { ino, read: from });
+    }
+    let complete = buf.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+
+    for line in String::from_utf8_lossy(&buf[..complete]).lines() {
+        let mut parts = line.split_whitespace();
+        let (Some(key), Some(offset), Some(elen), Some(accessed)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        let (Ok(offset), Ok(elen), Ok(accessed)) =
+            (offset.parse::<u64>(), elen.parse::<u64>(), accessed.parse::<u64>())
+        else {
+            continue;
+        };
+        // Skip records cut short by a crash mid-append.
+        if offset + elen <= pack_len {
+            index.insert(key.to_string(), PackEntry { offset, len: elen, accessed });
+        }
+    }
+    (index, IndexPos { ino, read: from + complete as u64 })
+}
+
+fn open_pack_file(dir: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(dir.join("thumbs.pack"))
+}
+
+impl Pack {
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = open_pack_file(dir)?;
+        let len = file.metadata()?.len();
+        let (index, idx_pos) = read_index(dir, len, 0);
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+            len,
+            index,
+            idx_pos,
+            dirty: false,
+        })
+    }
+
+    /// Takes the cross-process write lock; it is released when the returned file is dropped.
+    fn lock(&self) -> io::Result<File> {
+        let f = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.dir.join("thumbs.lock"))?;
+        loop {
+            if unsafe { libc::flock(f.as_raw_fd(), libc::LOCK_EX) } == 0 {
+                return Ok(f);
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    /// Catches up with other processes; call with the lock held. If the pack was compacted
+    /// elsewhere it is reopened and the index on disk replaces ours (our offsets are void).
+    /// Otherwise entries added elsewhere are merged in, keeping the later access time; only
+    /// the lines appended since the last read are parsed unless the index was rewritten.
+    fn refresh(&mut self) -> io::Result<()> {
+        let on_disk = fs::metadata(self.pack_path())?;
+        if on_disk.ino() != self.file.metadata()?.ino() {
+            self.file = open_pack_file(&self.dir)?;
+            self.len = self.file.metadata()?.len();
+            (self.index, self.idx_pos) = read_index(&self.dir, self.len, 0);
+            return Ok(());
+        }
+
+        self.len = on_disk.len();
+        let from = match fs::metadata(self.index_path()) {
+            Ok(m) if m.ino() == self.idx_pos.ino && m.len() >= self.idx_pos.read => {
+                if m.len() == self.idx_pos.read {
+                    return Ok(());
+                }
+                self.idx_pos.read
+            }
+            _ => 0,
+        };
+        let (added, pos) = read_index(&self.dir, self.len, from);
+        self.idx_pos = pos;
+        for (k, e) in added {
+            self.index
+                .entry(k)
+                .and_modify(|ours| ours.accessed = ours.accessed.max(e.accessed))
+                .or_insert(e);
+        }
+        Ok(())
+    }
+
+    fn pack_path(&self) -> PathBuf {
+        self.dir.join("thumbs.pack")
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.dir.join("thumbs.idx")
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &PackEntry)> {
+        self.index.iter()
+    }
+
+    pub fn file_len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn get(&mut self, key: &str) -> Option<Vec<u8>> {
+        let entry = self.index.get_mut(key)?;
+        let mut buf = vec![0u8; entry.len as usize];
+        self.file.read_exact_at(&mut buf, entry.offset).ok()?;
+
+        entry.accessed = now_secs();
+        self.dirty = true;
+        Some(buf)
+    }
+
+    pub fn insert(&mut self, key: &str, data: &[u8]) -> io::Result<()> {
+        let _lock = self.lock()?;
+        self.refresh()?;
+
+        let entry = PackEntry {
+            offset: self.len,
+            len: data.len() as u64,
+            accessed: now_secs(),
+        };
+
+        self.file.write_all(data)?;
+        self.len += entry.len;
+
+        let mut idx = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(self.index_path())?;
+        idx.write_all(index_line(key, &entry).as_bytes())?;
+        // We were caught up under the lock, so our own line is the only thing new.
+        let m = idx.metadata()?;
+        self.idx_pos = IndexPos { ino: m.ino(), read: m.len() };
+
+        self.index.insert(key.to_string(), entry);
+        Ok(())
+    }
+
+    /// Drops every entry for which `remove` returns true, then compacts. Returns the number of
+    /// entries removed and their combined size.
+    pub fn remove_where<F>(&mut self, mut remove: F) -> io::Result<(usize, u64)>
+    where
+        F: FnMut(&str, &PackEntry) -> bool,
+    {
+        let _lock = self.lock()?;
+        self.refresh()?;
+
+        let mut removed = 0usize;
+        let mut bytes = 0u64;
+        self.index.retain(|k, e| {
+            if remove(k, e) {
+                removed += 1;
+                bytes += e.len;
+                false
+            } else {
+                true
+            }
+        });
+
+        if removed > 0 {
+            self.compact()?;
+        }
+        Ok((removed, bytes))
+    }
+
+    /// Rewrites the pack without dead records once they make up more than half of it. Call
+    /// with the lock held.
+    fn compact(&mut self) -> io::Result<()> {
+        let live: u64 = self.index.values().map(|e| e.len).sum();
+        if live * 2 > self.len {
+            return self.write_index();
+        }
+
+        let tmp_path = self.dir.join("thumbs.pack.tmp");
+        let mut tmp = File::create(&tmp_path)?;
+        let mut offset = 0u64;
+        let mut buf = Vec::new();
+        for e in self.index.values_mut() {
+            buf.resize(e.len as usize, 0);
+            self.file.read_exact_at(&mut buf, e.offset)?;
+            tmp.write_all(&buf)?;
+            e.offset = offset;
+            offset += e.len;
+        }
+        tmp.sync_all()?;
+        drop(tmp);
+
+        fs::rename(&tmp_path, self.pack_path())?;
+        self.file = OpenOptions::new().read(true).append(true).open(self.pack_path())?;
+        self.len = offset;
+
+        self.write_index()
+    }
+
+    /// Writes the full index (including access times) atomically, merged with whatever other
+    /// processes added since we last looked.
+    pub fn save_index(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let _lock = self.lock()?;
+        self.refresh()?;
+        self.write_index()
+    }
+
+    /// Replaces `thumbs.idx` with our index. Call with the lock held.
+    fn write_index(&mut self) -> io::Result<()> {
+        let mut data = String::new();
+        for (k, e) in self.index.iter() {
+            data.push_str(&index_line(k, e));
+        }
+
+        crate::config::write_atomic(&self.index_path(), data.as_bytes())?;
+        self.idx_pos = IndexPos {
+            ino: fs::metadata(self.index_path())?.ino(),
+            read: data.len() as u64,
+        };
+        self.dirty = false;
+        Ok(())
+    }
+}
@@ -1,8 +1,9 @@
+use crate::commands::PreviewEvent;
 use crate::config::Config;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
@@ -11,12 +12,110 @@ struct DedupeKey {
     hash: blake3::Hash,
 }
 
-pub async fn run_dedupe(cfg: Config) {
-    let images = crate::scanner::scan_directories(cfg.folders.clone()).await;
-    if images.is_empty() {
-        println!("No images found.");
-        return;
+/// What happens to the copies that are not kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupeAction {
+    #[default]
+    Delete,
+    Hardlink,
+    Symlink,
+}
+
+impl DedupeAction {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "hard" | "hardlink" => Some(Self::Hardlink),
+            "sym" | "symlink" | "soft" => Some(Self::Symlink),
+            _ => None,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            Self::Delete => "Delete",
+            Self::Hardlink => "Replace with hardlinks",
+            Self::Symlink => "Replace with symlinks",
+        }
+    }
+
+    fn verb(&self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Hardlink => "hardlink",
+            Self::Symlink => "symlink",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub size: u64,
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileInfo {
+    pub len: u64,
+    pub dimensions: Option<(u32, u32)>,
+    pub format: Option<image::ImageFormat>,
+    pub folder: Option<PathBuf>,
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+}
+
+impl FileInfo {
+    pub fn resolution(&self) -> String {
+        match self.dimensions {
+            Some((w, h)) => format!("{w}x{h}"),
+            None => String::from("unknown resolution"),
+        }
+    }
+
+    pub fn format_name(&self) -> &'static str {
+        match self.format {
+            Some(image::ImageFormat::Png) => "PNG",
+            Some(image::ImageFormat::Jpeg) => "JPEG",
+            Some(image::ImageFormat::WebP) => "WebP",
+            Some(_) => "other",
+            None => "unknown format",
+        }
+    }
+
+    pub fn folder_name(&self) -> String {
+        match &self.folder {
+            Some(f) => f.display().to_string(),
+            None => String::from("outside configured folders"),
+        }
+    }
+}
+
+/// Reads dimensions and format from the image header only, so this stays cheap for large files.
+pub fn file_info(path: &Path, folders: &[PathBuf]) -> FileInfo {
+    let meta = std::fs::metadata(path).ok();
+    let reader = image::io::Reader::open(path)
+        .ok()
+        .and_then(|r| r.with_guessed_format().ok());
+    let format = reader.as_ref().and_then(|r| r.format());
+    let dimensions = reader.and_then(|r| r.into_dimensions().ok());
+
+    let folder = folders
+        .iter()
+        .filter(|f| path.starts_with(f))
+        .max_by_key(|f| f.components().count())
+        .cloned();
+
+    FileInfo {
+        len: meta.as_ref().map(|m| m.len()).unwrap_or(0),
+        dimensions,
+        format,
+        folder,
+        created: meta.as_ref().and_then(|m| m.created().ok()),
+        modified: meta.as_ref().and_then(|m| m.modified().ok()),
     }
+}
+
+pub async fn find_duplicate_groups(folders: Vec<PathBuf>) -> Vec<DuplicateGroup> {
+    let images = crate::scanner::scan_directories(folders).await;
 
     let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
     for p in images {
@@ -40,23 +139,30 @@ pub async fn run_dedupe(cfg: Config) {
         }
     }
 
-    let mut dup_groups: Vec<(u64, blake3::Hash, Vec<PathBuf>)> = groups
+    let mut dup_groups: Vec<DuplicateGroup> = groups
         .into_iter()
-        .filter_map(|(k, v)| if v.len() >= 2 { Some((k.size, k.hash, v)) } else { None })
+        .filter_map(|(k, v)| if v.len() >= 2 { Some(DuplicateGroup { size: k.size, files: v }) } else { None })
         .collect();
 
+    dup_groups.sort_by_key(|g| std::cmp::Reverse(g.size));
+    dup_groups
+}
+
+#[allow(clippy::collapsible_if)]
+pub async fn run_dedupe(cfg: Config, action: DedupeAction) {
+    let dup_groups = find_duplicate_groups(cfg.folders.clone()).await;
+
     if dup_groups.is_empty() {
         println!("No exact duplicates found.");
         return;
     }
 
-    dup_groups.sort_by(|a, b| b.0.cmp(&a.0));
-
     let mut total_deleted = 0usize;
+    let mut total_linked = 0usize;
     let mut bytes_reclaimed: u64 = 0;
     let mut groups_processed = 0usize;
 
-    'outer: for (idx, (size, _hash, mut files)) in dup_groups.into_iter().enumerate() {
+    'outer: for (idx, DuplicateGroup { size, mut files }) in dup_groups.into_iter().enumerate() {
         files.retain(|p| p.exists());
         if files.len() < 2 {
             continue;
@@ -66,8 +172,16 @@ pub async fn run_dedupe(cfg: Config) {
         loop {
             println!("\nDuplicate group {} ({} file(s), {})", idx + 1, files.len(), format_size(size));
             for (i, p) in files.iter().enumerate() {
-                let mt = read_modified_time(p);
-                println!("  [{}] {} | modified {}", i + 1, p.display(), mt);
+                let info = file_info(p, &cfg.folders);
+                println!("  [{}] {}", i + 1, p.display());
+                println!(
+                    "      {} {} | created {} | modified {} | in {}",
+                    info.resolution(),
+                    info.format_name(),
+                    format_time(info.created),
+                    format_time(info.modified),
+                    info.folder_name()
+                );
             }
             println!("Options: k <n>=keep n, kn=keep newest, ko=keep oldest, p <n>=preview n, s=skip, q=quit");
             print!("dedupe> ");
@@ -94,15 +208,16 @@ pub async fn run_dedupe(cfg: Config) {
                                 let keep_path = files[keep_index].clone();
 
                                 if !confirm_action(&format!(
-                                    "Delete {} other file(s) and keep '{}' [y/N] ",
+                                    "{} {} other file(s) and keep '{}' [y/N] ",
+                                    action.describe(),
                                     files.len() - 1,
                                     keep_path.display()
                                 )) {
                                     continue;
                                 }
-                                
-                                let (del_count, bytes) = delete_all_except(&files, keep_index, size);
-                                total_deleted += del_count;
+
+                                let (count, bytes) = dedupe_all_except(&files, keep_index, size, action);
+                                tally(action, count, &mut total_deleted, &mut total_linked);
                                 bytes_reclaimed += bytes;
                                 break;
                             }
@@ -112,16 +227,16 @@ pub async fn run_dedupe(cfg: Config) {
                 }
                 "kn" => {
                     if let Some(keep_idx) = newest_index(&files) {
-                        let (del_count, bytes) = delete_all_except(&files, keep_idx, size);
-                        total_deleted += del_count;
+                        let (count, bytes) = dedupe_all_except(&files, keep_idx, size, action);
+                        tally(action, count, &mut total_deleted, &mut total_linked);
                         bytes_reclaimed += bytes;
                         break;
                     }
                 }
                 "ko" => {
                     if let Some(keep_idx) = oldest_index(&files) {
-                        let (del_count, bytes) = delete_all_except(&files, keep_idx, size);
-                        total_deleted += del_count;
+                        let (count, bytes) = dedupe_all_except(&files, keep_idx, size, action);
+                        tally(action, count, &mut total_deleted, &mut total_linked);
                         bytes_reclaimed += bytes;
                         break;
                     }
@@ -131,16 +246,26 @@ pub async fn run_dedupe(cfg: Config) {
                         if let Ok(n) = nstr.parse::<usize>() {
                             if n >= 1 && n <= files.len() {
                                 let p = files[n - 1].clone();
-                                let deleted = crate::commands::open_preview(p.clone()).await;
-
-                                if deleted {
-                                    files.retain(|x| x != &p);
-                                    total_deleted += 1;
-                                    bytes_reclaimed += size;
-                                    if files.len() < 2 {
-                                        break;
+                                let events = crate::commands::open_preview(p, files.clone()).await;
+
+                                for event in events {
+                                    match event {
+                                        PreviewEvent::Deleted { path } => {
+                                            files.retain(|x| x != &path);
+                                            total_deleted += 1;
+                                            bytes_reclaimed += size;
+                                        }
+                                        PreviewEvent::Moved { from, to } => {
+                                            for f in files.iter_mut().filter(|f| **f == from) {
+                                                *f = to.clone();
+                                            }
+                                        }
+                                        _ => {}
                                     }
                                 }
+                                if files.len() < 2 {
+                                    break;
+                                }
                                 continue;
                             }
                         }
@@ -161,10 +286,17 @@ pub async fn run_dedupe(cfg: Config) {
         }
     }
 
+    let linked = match action {
+        DedupeAction::Delete => String::new(),
+        DedupeAction::Hardlink => format!(" hardlinked {} file(s),", total_linked),
+        DedupeAction::Symlink => format!(" symlinked {} file(s),", total_linked),
+    };
+
     println!(
-        "\nDedupe summary: processed {} group(s), deleted {} file(s), reclaimed {}.",
+        "\nDedupe summary: processed {} group(s), deleted {} file(s),{} reclaimed {}.",
         groups_processed,
         total_deleted,
+        linked,
         format_size(bytes_reclaimed)
     );
 }
@@ -180,7 +312,7 @@ fn confirm_action(prompt: &str) -> bool {
     false
 }
 
-fn newest_index(files: &[PathBuf]) -> Option<usize> {
+pub fn newest_index(files: &[PathBuf]) -> Option<usize> {
     let mut best: Option<(SystemTime, usize)> = None;
     for (i, p) in files.iter().enumerate() {
         let t = file_time(p).unwrap_or(UNIX_EPOCH);
@@ -195,7 +327,7 @@ fn newest_index(files: &[PathBuf]) -> Option<usize> {
     best.map(|(_, i)| i)
 }
 
-fn oldest_index(files: &[PathBuf]) -> Option<usize> {
+pub fn oldest_index(files: &[PathBuf]) -> Option<usize> {
     let mut best: Option<(SystemTime, usize)> = None;
     for (i, p) in files.iter().enumerate() {
         let t = file_time(p).unwrap_or(UNIX_EPOCH);
@@ -210,25 +342,69 @@ fn oldest_index(files: &[PathBuf]) -> Option<usize> {
     best.map(|(_, i)| i)
 }
 
-fn delete_all_except(files: &[PathBuf], keep_index: usize, size_per_file: u64) -> (usize, u64) {
-    let mut deleted = 0usize;
+fn tally(action: DedupeAction, count: usize, deleted: &mut usize, linked: &mut usize) {
+    match action {
+        DedupeAction::Delete => *deleted += count,
+        DedupeAction::Hardlink | DedupeAction::Symlink => *linked += count,
+    }
+}
+
+/// Deletes or links every file in the group except `keep_index`, returning how many were
+/// handled and how many bytes that freed.
+pub fn dedupe_all_except(
+    files: &[PathBuf],
+    keep_index: usize,
+    size_per_file: u64,
+    action: DedupeAction,
+) -> (usize, u64) {
+    let mut handled = 0usize;
     let mut bytes = 0u64;
+    let keep = &files[keep_index];
     for (i, p) in files.iter().enumerate() {
         if i == keep_index {
             continue;
         }
-        match std::fs::remove_file(p) {
+        let res = match action {
+            DedupeAction::Delete => std::fs::remove_file(p),
+            DedupeAction::Hardlink | DedupeAction::Symlink => link_duplicate(keep, p, action),
+        };
+        match res {
             Ok(_) => {
-                deleted += 1;
+                handled += 1;
                 bytes += size_per_file;
-                println!("Deleted {}", p.display());
+                match action {
+                    DedupeAction::Delete => println!("Deleted {}", p.display()),
+                    DedupeAction::Hardlink => println!("Hardlinked {} -> {}", p.display(), keep.display()),
+                    DedupeAction::Symlink => println!("Symlinked {} -> {}", p.display(), keep.display()),
+                }
             }
             Err(e) => {
-                eprintln!("Failed to delete {}: {}", p.display(), e);
+                eprintln!("Failed to {} {}: {}", action.verb(), p.display(), e);
             }
         }
     }
-    (deleted, bytes)
+    (handled, bytes)
+}
+
+/// Swaps `dup` for a link to `keep`. The link is created next to `dup` first and renamed over
+/// it, so a failure (e.g. hardlinking across filesystems) leaves the original file untouched.
+fn link_duplicate(keep: &Path, dup: &Path, action: DedupeAction) -> std::io::Result<()> {
+    let name = dup
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let tmp = dup.with_file_name(format!(".{}.wallpicker-link", name));
+    let _ = std::fs::remove_file(&tmp);
+
+    let created = match action {
+        DedupeAction::Hardlink => std::fs::hard_link(keep, &tmp),
+        _ => std::fs::canonicalize(keep).and_then(|target| std::os::unix::fs::symlink(target, &tmp)),
+    };
+    created?;
+
+    std::fs::rename(&tmp, dup).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
 }
 
 async fn compute_hash_key(path: PathBuf, size: u64) -> Option<DedupeKey> {
@@ -253,28 +429,43 @@ async fn compute_hash_key(path: PathBuf, size: u64) -> Option<DedupeKey> {
     .flatten()
 }
 
-fn file_time(p: &PathBuf) -> Option<SystemTime> {
+pub fn file_time(p: &PathBuf) -> Option<SystemTime> {
     std::fs::metadata(p)
         .ok()
         .and_then(|m| m.created().ok().or_else(|| m.modified().ok()))
 }
 
-fn read_modified_time(p: &PathBuf) -> String {
-    if let Some(t) = file_time(p) {
-        if let Ok(dur) = t.duration_since(UNIX_EPOCH) {
-            let secs = dur.as_secs();
-            let tm = chrono_like(secs);
-            return tm;
-        }
+pub fn format_time(t: Option<SystemTime>) -> String {
+    if let Some(t) = t
+        && let Ok(dur) = t.duration_since(UNIX_EPOCH)
+        && let Some(tm) = local_datetime(dur.as_secs())
+    {
+        return tm;
     }
     "unknown".into()
 }
 
-fn chrono_like(secs: u64) -> String {
-    format!("{}s", secs)
+/// Formats epoch seconds as `YYYY-MM-DD HH:MM:SS` in the local timezone.
+fn local_datetime(secs: u64) -> Option<String> {
+    let t = libc::time_t::try_from(secs).ok()?;
+    // SAFETY: `localtime_r` only writes into the `tm` we hand it and is thread-safe.
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&t, &mut tm) }.is_null() {
+        return None;
+    }
+
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    ))
 }
 
-fn format_size(bytes: u64) -> String {
+pub fn format_size(bytes: u64) -> String {
     const KB: f64 = 1024.0;
     const MB: f64 = KB * 1024.0;
     const GB: f64 = MB * 1024.0;
@@ -0,0 +1,135 @@
+use crate::config::Config;
+use crate::image::{ThumbSpec, ensure_thumb_cached};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+enum Outcome {
+    Generated,
+    Skipped,
+    Failed(PathBuf, u32, String),
+}
+
+/// Builds thumbnails for every image at each of `sizes` (the configured size if empty),
+/// spreading the work over one thread per core.
+pub async fn run_generate(cfg: Config, sizes: Vec<u32>) {
+    let imgs = crate::scanner::scan_directories(cfg.folders.clone()).await;
+
+    let sizes = if sizes.is_empty() { vec![cfg.thumbnail_size] } else { sizes };
+    let specs: Vec<ThumbSpec> = sizes.iter().map(|s| ThumbSpec::new(&cfg, *s)).collect();
+
+    println!(
+        "Found {} image(s) to generate thumbnails for at size(s) {}.",
+        imgs.len(),
+        sizes.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+    );
+
+    let jobs: Vec<(PathBuf, ThumbSpec)> = imgs
+        .into_iter()
+        .flat_map(|p| specs.iter().map(move |s| (p.clone(), *s)))
+        .collect();
+
+    let (generated, skipped, failures) = tokio::task::spawn_blocking(move || generate_all(jobs))
+        .await
+        .unwrap_or((0, 0, Vec::new()));
+
+    let _ = crate::cache::flush_stats();
+
+    println!(
+        "Generated {} thumbnail(s); {} already cached; {} failed",
+        generated,
+        skipped,
+        failures.len()
+    );
+    for (path, size, err) in failures {
+        println!("  failed: {} ({}px): {}", path.display(), size, err);
+    }
+}
+
+fn generate_all(jobs: Vec<(PathBuf, ThumbSpec)>) -> (usize, usize, Vec<(PathBuf, u32, String)>) {
+    let total = jobs.len();
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(total.max(1));
+
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel::<Outcome>();
+
+    let mut generated = 0usize;
+    let mut skipped = 0usize;
+    let mut failures: Vec<(PathBuf, u32, String)> = Vec::new();
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            let tx = tx.clone();
+            let jobs = &jobs;
+            let next = &next;
+            scope.spawn(move || {
+                loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some((path, spec)) = jobs.get(i) else { break };
+
+                    let outcome = match ensure_thumb_cached(path, *spec) {
+                        Ok(true) => Outcome::Generated,
+                        Ok(false) => Outcome::Skipped,
+                        Err(e) => Outcome::Failed(path.clone(), spec.size, e.to_string()),
+                    };
+                    if tx.send(outcome).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        let started = Instant::now();
+        let mut last_print: Option<Instant> = None;
+        let mut done = 0usize;
+
+        for outcome in rx {
+            done += 1;
+            match outcome {
+                Outcome::Generated => generated += 1,
+                Outcome::Skipped => skipped += 1,
+                Outcome::Failed(p, size, e) => failures.push((p, size, e)),
+            }
+
+            let due = last_print.is_none_or(|t| t.elapsed() >= Duration::from_millis(100));
+            if due || done == total {
+                print_progress(done, total, started.elapsed());
+                last_print = Some(Instant::now());
+            }
+        }
+    });
+
+    if total > 0 {
+        println!();
+    }
+
+    (generated, skipped, failures)
+}
+
+fn print_progress(done: usize, total: usize, elapsed: Duration) {
+    let secs = elapsed.as_secs_f64();
+    let rate = if secs > 0.0 { done as f64 / secs } else { 0.0 };
+    let eta = if rate > 0.0 {
+        format_duration(Duration::from_secs_f64((total - done) as f64 / rate))
+    } else {
+        String::from("--:--")
+    };
+
+    print!("\r[{done}/{total}] {rate:.1} img/s, ETA {eta}   ");
+    let _ = std::io::stdout().flush();
+}
+
+fn format_duration(d: Duration) -> String {
+    let s = d.as_secs();
+    if s >= 3600 {
+        format!("{}:{:02}:{:02}", s / 3600, (s / 60) % 60, s % 60)
+    } else {
+        format!("{:02}:{:02}", s / 60, s % 60)
+    }
+}
@@ -1,7 +1,9 @@
 pub mod wallpaper;
 pub mod preview;
 pub mod dedupe;
+pub mod generate;
 
 pub use wallpaper::{set_random_wallpaper, set_random_wallpaper_via_wallhaven, set_wallpaper};
-pub use preview::open_preview;
+pub use preview::{PreviewEvent, open_preview};
 pub use dedupe::run_dedupe;
+pub use generate::run_generate;
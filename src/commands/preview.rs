@@ -1,24 +1,60 @@
+use serde::{Deserialize, Serialize};
+use std::io::Write;
 use std::path::PathBuf;
+use std::process::Stdio;
 
-pub async fn open_preview(path: PathBuf) -> bool {
+/// Something the user did in the preview that the picker has to know about. The preview
+/// process prints one JSON object per event on stdout as it happens. There is no event for
+/// tagging: tags come from Wallhaven and can't be edited in the app.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PreviewEvent {
+    Deleted { path: PathBuf },
+    WallpaperSet { path: PathBuf },
+    /// `favourite` is false when the mark was taken off again.
+    Favourited { path: PathBuf, favourite: bool },
+    Moved { from: PathBuf, to: PathBuf },
+}
+
+impl PreviewEvent {
+    /// Writes the event to stdout for the picker to pick up.
+    pub fn report(&self) {
+        if let Ok(line) = serde_json::to_string(self) {
+            let mut out = std::io::stdout().lock();
+            let _ = writeln!(out, "{line}");
+            let _ = out.flush();
+        }
+    }
+}
+
+/// Opens the preview on `path` and returns everything that happened in it. `list` is the set
+/// of images to browse with Left/Right; it is handed to the preview process on stdin.
+pub async fn open_preview(path: PathBuf, list: Vec<PathBuf>) -> Vec<PreviewEvent> {
     let res = tokio::task::spawn_blocking(move || {
-        if let Ok(exe) = std::env::current_exe() {
-            let mut cmd = std::process::Command::new(exe);
-            cmd.arg("--preview").arg(&path);
-            match cmd.status() {
-                Ok(status) => {
-                    if let Some(code) = status.code() {
-                        return code == 10;
-                    }
-                    false
-                }
-                Err(_) => false,
+        let exe = std::env::current_exe().ok()?;
+        let mut cmd = std::process::Command::new(exe);
+        cmd.arg("--preview").arg(&path);
+        if !list.is_empty() {
+            cmd.arg("--browse").stdin(Stdio::piped());
+        }
+        cmd.stdout(Stdio::piped());
+
+        let mut child = cmd.spawn().ok()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            for p in list.iter() {
+                let _ = writeln!(stdin, "{}", p.display());
             }
-        } else {
-            false
         }
+
+        let output = child.wait_with_output().ok()?;
+        let events = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|l| serde_json::from_str(l).ok())
+            .collect();
+
+        Some(events)
     })
     .await;
 
-    res.unwrap_or(false)
+    res.ok().flatten().unwrap_or_default()
 }
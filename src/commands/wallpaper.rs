@@ -1,20 +1,27 @@
 use crate::config::Config;
-use crate::wallpaper::copy_to_current_wallpaper;
+use crate::wallhaven::WallhavenError;
+use crate::wallpaper::{copy_to_current_wallpaper, prepare_for_screen};
 use std::path::PathBuf;
 
-pub async fn set_wallpaper(path: PathBuf, copy_to_tmp: bool) -> () {
-    if copy_to_tmp {
-        let _ = copy_to_current_wallpaper(&path);
-    }
+/// Sets `path` as the wallpaper using its configured fill mode and background colour.
+pub async fn set_wallpaper(path: PathBuf, cfg: Config) -> () {
+    let placement = cfg.placement_for(&path);
 
-    let _ = tokio::task::spawn_blocking(move || crate::platform::set_wallpaper(&path)).await;
+    let _ = tokio::task::spawn_blocking(move || {
+        let target = prepare_for_screen(&path, placement);
+        if cfg.copy_to_tmp {
+            let _ = copy_to_current_wallpaper(&target);
+        }
+        crate::platform::set_wallpaper(&target)
+    })
+    .await;
 }
 
 pub async fn set_random_wallpaper(cfg: Config) -> Option<PathBuf> {
     match crate::wallpaper::find_random_wallpaper(&cfg).await {
         Ok(Some(path)) => {
             let ret = path.clone();
-            set_wallpaper(path.clone(), cfg.copy_to_tmp).await;
+            set_wallpaper(path.clone(), cfg).await;
 
             Some(ret)
         }
@@ -22,13 +29,8 @@ pub async fn set_random_wallpaper(cfg: Config) -> Option<PathBuf> {
     }
 }
 
-pub async fn set_random_wallpaper_via_wallhaven(cfg: Config) -> Option<PathBuf> {
-    match crate::wallhaven::fetch_wallhaven_wallpaper(&cfg).await {
-        Ok(Some(path)) => {
-            let ret = path.clone();
-            set_wallpaper(path.clone(), cfg.copy_to_tmp).await;
-            Some(ret)
-        }
-        _ => None,
-    }
+pub async fn set_random_wallpaper_via_wallhaven(cfg: Config) -> Result<PathBuf, WallhavenError> {
+    let path = crate::wallhaven::fetch_wallhaven_wallpaper(&cfg).await?;
+    set_wallpaper(path.clone(), cfg).await;
+    Ok(path)
 }
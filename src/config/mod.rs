@@ -1,6 +1,8 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use std::io::Read;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,8 +16,322 @@ pub struct Config {
     pub wallhaven_purity: String,
     #[serde(default = "default_wallhaven_resolution")]
     pub wallhaven_resolution: String,
+    /// Search query: tags, `-tag`, `@user`, `id:<n>`, `like:<id>`.
+    #[serde(default)]
+    pub wallhaven_query: String,
+    /// Exact resolutions, comma-separated (`1920x1080,2560x1440`).
+    #[serde(default)]
+    pub wallhaven_resolutions: String,
+    /// `landscape`, `portrait` or exact ratios like `16x9`, comma-separated.
+    #[serde(default = "default_wallhaven_ratios")]
+    pub wallhaven_ratios: String,
+    /// One colour from Wallhaven's palette, as hex (`336600`).
+    #[serde(default)]
+    pub wallhaven_colors: String,
+    #[serde(default = "default_wallhaven_sorting")]
+    pub wallhaven_sorting: String,
+    #[serde(default)]
+    pub wallhaven_order: String,
+    /// Period for `toplist` sorting: `1d`, `3d`, `1w`, `1M`, `3M`, `6M`, `1y`.
+    #[serde(default)]
+    pub wallhaven_top_range: String,
+    /// Seed for `random` sorting, so pages of one random search don't overlap.
+    #[serde(default)]
+    pub wallhaven_seed: String,
+    /// Downloads larger than this are aborted; 0 = no limit.
+    #[serde(default = "default_wallhaven_max_download_mb")]
+    pub wallhaven_max_download_mb: u64,
+    /// Wallhaven results never to pick at random.
+    #[serde(default)]
+    pub wallhaven_blacklist: Blacklist,
+    /// Seconds to wait for Wallhaven to connect and answer API requests (image downloads
+    /// only time out while connecting); 0 = no timeout.
+    #[serde(default = "default_wallhaven_timeout_secs")]
+    pub wallhaven_timeout_secs: u64,
+    /// Proxy for all Wallhaven traffic (`http://host:port`, `socks5://…`); empty uses the
+    /// usual `HTTPS_PROXY`-style environment variables.
+    #[serde(default)]
+    pub wallhaven_proxy: String,
+    /// PEM bundle of extra root certificates, e.g. for an intercepting proxy.
+    #[serde(default)]
+    pub wallhaven_ca_bundle: Option<PathBuf>,
+    /// Where the API lives; `/api/v1/…` is appended.
+    #[serde(default = "default_wallhaven_base_url")]
+    pub wallhaven_base_url: String,
     #[serde(default)]
     pub copy_to_tmp: bool,
+    #[serde(default = "default_thumbnail_size")]
+    pub thumbnail_size: u32,
+    #[serde(default)]
+    pub tile_aspect: TileAspect,
+    #[serde(default)]
+    pub thumbnail_mode: ThumbnailMode,
+    #[serde(default)]
+    pub cache_format: CacheFormat,
+    #[serde(default)]
+    pub cache_keying: CacheKeying,
+    #[serde(default = "default_cache_max_mb")]
+    pub cache_max_mb: u64,
+    #[serde(default)]
+    pub cache_max_entries: usize,
+    #[serde(default = "default_tile_spacing")]
+    pub tile_spacing: u16,
+    #[serde(default = "default_tile_padding")]
+    pub tile_padding: u16,
+    /// Optional screenshot-sized PNG (transparent except for bars/panels) drawn over the
+    /// preview's desktop simulation.
+    #[serde(default)]
+    pub desktop_mockup: Option<PathBuf>,
+    #[serde(default)]
+    pub fill_mode: FillMode,
+    /// Colour behind the image where it doesn't cover the screen, as `#rrggbb`.
+    #[serde(default = "default_background_color")]
+    pub background_color: String,
+    /// Per-image overrides of `fill_mode` / `background_color`, keyed by path.
+    #[serde(default)]
+    pub placements: HashMap<PathBuf, PlacementOverride>,
+    /// Images marked as favourites in the preview.
+    #[serde(default)]
+    pub favourites: Vec<PathBuf>,
+}
+
+/// Shape of the tiles in the picker grid. `Square` and `Wide` crop the thumbnail to fill the
+/// tile, `Native` shows the whole image at its own aspect ratio.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TileAspect {
+    #[default]
+    #[serde(rename = "square")]
+    Square,
+    #[serde(rename = "16:9", alias = "wide")]
+    Wide,
+    #[serde(rename = "native")]
+    Native,
+}
+
+/// How thumbnails are cut from the source image. `Crop` and `Smart` produce thumbnails in the
+/// monitor's aspect ratio: `Crop` takes the centre, `Smart` the most detailed region.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailMode {
+    #[default]
+    Fit,
+    Crop,
+    Smart,
+}
+
+/// On-disk layout of the thumbnail cache: one PNG per thumbnail, or a single append-only
+/// pack file plus index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheFormat {
+    #[default]
+    Files,
+    Pack,
+}
+
+/// What a cached thumbnail is keyed on. `Path` uses the file's path, mtime and size;
+/// `Content` uses a fingerprint of the file's bytes, so renames and moves keep their thumbnails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheKeying {
+    #[default]
+    Path,
+    Content,
+}
+
+/// How a wallpaper is placed on a screen whose aspect ratio differs from the image's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillMode {
+    /// Scale to cover the screen, cropping the overflow.
+    #[default]
+    Fill,
+    /// Scale to fit inside the screen, leaving bars.
+    Fit,
+    /// Unscaled, centred.
+    Center,
+    /// Unscaled, repeated from the top-left corner.
+    Tile,
+    /// Scaled to the screen size, ignoring aspect ratio.
+    Stretch,
+}
+
+impl FillMode {
+    pub const ALL: [FillMode; 5] = [
+        FillMode::Fill,
+        FillMode::Fit,
+        FillMode::Center,
+        FillMode::Tile,
+        FillMode::Stretch,
+    ];
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FillMode::Fill => "fill",
+            FillMode::Fit => "fit",
+            FillMode::Center => "center",
+            FillMode::Tile => "tile",
+            FillMode::Stretch => "stretch",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlacementOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fill_mode: Option<FillMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+}
+
+/// Wallhaven tags, uploaders and wallpaper ids to keep out of random picks. Matching ignores case.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Blacklist {
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub uploaders: Vec<String>,
+    #[serde(default)]
+    pub ids: Vec<String>,
+}
+
+impl Blacklist {
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    pub fn has_uploader(&self, name: &str) -> bool {
+        self.uploaders.iter().any(|u| u.eq_ignore_ascii_case(name))
+    }
+
+    pub fn has_id(&self, id: &str) -> bool {
+        self.ids.iter().any(|i| i.eq_ignore_ascii_case(id))
+    }
+
+    pub fn add_uploader(&mut self, name: &str) {
+        if !self.has_uploader(name) {
+            self.uploaders.push(name.to_string());
+        }
+    }
+
+    pub fn add_id(&mut self, id: &str) {
+        if !self.has_id(id) {
+            self.ids.push(id.to_string());
+        }
+    }
+}
+
+/// The effective fill mode and background for one image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub mode: FillMode,
+    pub background: [u8; 3],
+}
+
+impl Config {
+    pub fn placement_for(&self, path: &Path) -> Placement {
+        let o = self.placements.get(path);
+        // An unparsable override falls back to the global colour rather than to black.
+        let background = o
+            .and_then(|o| o.background_color.as_deref())
+            .and_then(parse_hex_color)
+            .or_else(|| parse_hex_color(&self.background_color))
+            .unwrap_or([0, 0, 0]);
+
+        Placement {
+            mode: o.and_then(|o| o.fill_mode).unwrap_or(self.fill_mode),
+            background,
+        }
+    }
+
+    pub fn is_favourite(&self, path: &Path) -> bool {
+        self.favourites.iter().any(|p| p == path)
+    }
+
+    /// Adds or removes `path` from the favourites, returning whether it is one now.
+    pub fn toggle_favourite(&mut self, path: &Path) -> bool {
+        if self.is_favourite(path) {
+            self.favourites.retain(|p| p != path);
+            false
+        } else {
+            self.favourites.push(path.to_path_buf());
+            true
+        }
+    }
+
+    /// Carries per-image settings over to a file's new path after it was moved.
+    pub fn rename_path(&mut self, from: &Path, to: &Path) {
+        if let Some(o) = self.placements.remove(from) {
+            self.placements.insert(to.to_path_buf(), o);
+        }
+        for p in self.favourites.iter_mut().filter(|p| *p == from) {
+            *p = to.to_path_buf();
+        }
+    }
+
+    /// Records `mode` for `path`, dropping the override when it matches the global setting.
+    pub fn set_fill_mode_for(&mut self, path: &Path, mode: FillMode) {
+        let o = self.placements.entry(path.to_path_buf()).or_default();
+        o.fill_mode = (mode != self.fill_mode).then_some(mode);
+        if *o == PlacementOverride::default() {
+            self.placements.remove(path);
+        }
+    }
+}
+
+/// Parses `#rrggbb` (the `#` is optional).
+pub fn parse_hex_color(s: &str) -> Option<[u8; 3]> {
+    let hex = s.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn default_background_color() -> String {
+    String::from("#000000")
+}
+
+fn default_thumbnail_size() -> u32 {
+    crate::image::DEFAULT_THUMB_SIZE
+}
+
+fn default_cache_max_mb() -> u64 {
+    512
+}
+
+fn default_tile_spacing() -> u16 {
+    1
+}
+
+fn default_tile_padding() -> u16 {
+    10
+}
+
+fn default_wallhaven_max_download_mb() -> u64 {
+    50
+}
+
+fn default_wallhaven_timeout_secs() -> u64 {
+    30
+}
+
+fn default_wallhaven_base_url() -> String {
+    String::from("https://wallhaven.cc")
+}
+
+fn default_wallhaven_ratios() -> String {
+    String::from("landscape")
+}
+
+fn default_wallhaven_sorting() -> String {
+    String::from("random")
 }
 
 fn default_wallhaven_resolution() -> String {
@@ -33,7 +349,35 @@ impl Default for Config {
             wallhaven_purity: String::from("100"),
             wallhaven_categories: String::from("111"),
             wallhaven_resolution: default_wallhaven_resolution(),
+            wallhaven_query: String::new(),
+            wallhaven_resolutions: String::new(),
+            wallhaven_ratios: default_wallhaven_ratios(),
+            wallhaven_colors: String::new(),
+            wallhaven_sorting: default_wallhaven_sorting(),
+            wallhaven_order: String::new(),
+            wallhaven_top_range: String::new(),
+            wallhaven_seed: String::new(),
+            wallhaven_max_download_mb: default_wallhaven_max_download_mb(),
+            wallhaven_blacklist: Blacklist::default(),
+            wallhaven_timeout_secs: default_wallhaven_timeout_secs(),
+            wallhaven_proxy: String::new(),
+            wallhaven_ca_bundle: None,
+            wallhaven_base_url: default_wallhaven_base_url(),
             copy_to_tmp: false,
+            thumbnail_size: default_thumbnail_size(),
+            tile_aspect: TileAspect::default(),
+            thumbnail_mode: ThumbnailMode::default(),
+            cache_format: CacheFormat::default(),
+            cache_keying: CacheKeying::default(),
+            cache_max_mb: default_cache_max_mb(),
+            cache_max_entries: 0,
+            tile_spacing: default_tile_spacing(),
+            tile_padding: default_tile_padding(),
+            desktop_mockup: None,
+            fill_mode: FillMode::default(),
+            background_color: default_background_color(),
+            placements: HashMap::new(),
+            favourites: Vec::new(),
         }
     }
 }
@@ -74,6 +418,23 @@ pub fn load_or_create_config() -> Config {
     cfg
 }
 
+/// Reports background colours that don't parse, once per run. They are left as they are so a
+/// later save doesn't overwrite them; [`Config::placement_for`] falls back in the meantime.
+fn warn_invalid_colors(cfg: &Config) {
+    static WARNED: std::sync::Once = std::sync::Once::new();
+    WARNED.call_once(|| {
+        if parse_hex_color(&cfg.background_color).is_none() {
+            eprintln!("Invalid background_color {:?}, using black", cfg.background_color);
+        }
+        for (path, o) in &cfg.placements {
+            if let Some(c) = o.background_color.as_deref().filter(|c| parse_hex_color(c).is_none()) {
+                eprintln!("Invalid background_color {c:?} for {}, using the global colour", path.display());
+            }
+        }
+    });
+}
+
+#[allow(clippy::collapsible_if)]
 pub fn load_config() -> Config {
     let cf = config_file_path();
 
@@ -88,6 +449,13 @@ pub fn load_config() -> Config {
                     cfg.wallhaven_api_key = String::new();
                 }
 
+                // 0 would divide by zero in the grid layout; huge sizes bypass the zoom limits.
+                cfg.thumbnail_size = cfg
+                    .thumbnail_size
+                    .clamp(crate::image::MIN_THUMB_SIZE, crate::image::MAX_THUMB_SIZE);
+
+                warn_invalid_colors(&cfg);
+
                 return cfg;
             }
         }
@@ -106,3 +474,33 @@ pub fn save_config(cfg: &Config) -> std::io::Result<()> {
     let data = serde_json::to_string_pretty(cfg).unwrap_or_else(|_| String::from("{}"));
     fs::write(cf, data)
 }
+
+/// Reads a JSON state file; `None` if it is missing or unreadable.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let s = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&s).ok()
+}
+
+/// Reads a JSON state file, starting afresh if it is missing or unreadable.
+pub fn read_json_or_default<T: DeserializeOwned + Default>(path: &Path) -> T {
+    read_json(path).unwrap_or_default()
+}
+
+/// Replaces `path` with `data` through a temporary file and a rename, so neither a crash nor
+/// a concurrent reader ever sees a half-written file. Creates the parent directory.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let tmp = path.with_file_name(name);
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path)
+}
+
+/// Writes `value` to the JSON state file at `path`; see [`write_atomic`].
+pub fn write_json_atomic<T: Serialize + ?Sized>(path: &Path, value: &T) -> io::Result<()> {
+    let data = serde_json::to_vec(value).map_err(io::Error::other)?;
+    write_atomic(path, &data)
+}
@@ -1,36 +1,263 @@
 use iced::widget::image::Handle as IcedImageHandle;
-use std::fs;
+use image::{DynamicImage, GenericImageView, RgbaImage};
 use std::path::{Path, PathBuf};
 use std::io;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::OnceLock;
 
-pub const THUMB_SIZE: u32 = 200;
+use crate::config::{Config, FillMode, Placement, ThumbnailMode};
 
-pub async fn load_thumb(path: PathBuf) -> Option<IcedImageHandle> {
-    tokio::task::spawn_blocking(move || {
-        let cache_path = crate::cache::cached_thumb_path(&path, THUMB_SIZE);
-
-        if let Some(cache) = cache_path {
-            if cache.exists() {
-                if let Ok(img) = image::open(&cache) {
-                    let rgba = img.to_rgba8();
-                    let (w, h) = rgba.dimensions();
-                    return Some(IcedImageHandle::from_rgba(w, h, rgba.into_raw()));
+pub const DEFAULT_THUMB_SIZE: u32 = 200;
+pub const MIN_THUMB_SIZE: u32 = 100;
+pub const MAX_THUMB_SIZE: u32 = 500;
+pub const ZOOM_STEP: u32 = 50;
+
+/// Every size the grid can zoom to; thumbnails are only ever cached at these sizes (plus the
+/// configured one), so zooming reuses the cache instead of piling up one-off variants.
+pub fn zoom_sizes() -> impl Iterator<Item = u32> {
+    (MIN_THUMB_SIZE..=MAX_THUMB_SIZE).step_by(ZOOM_STEP as usize)
+}
+
+pub fn zoom_in(size: u32) -> u32 {
+    ((size / ZOOM_STEP + 1) * ZOOM_STEP).clamp(MIN_THUMB_SIZE, MAX_THUMB_SIZE)
+}
+
+pub fn zoom_out(size: u32) -> u32 {
+    (size.div_ceil(ZOOM_STEP).saturating_sub(1) * ZOOM_STEP).clamp(MIN_THUMB_SIZE, MAX_THUMB_SIZE)
+}
+
+/// Everything that determines what a cached thumbnail looks like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ThumbSpec {
+    pub size: u32,
+    pub mode: ThumbnailMode,
+    pub aspect: (u32, u32),
+}
+
+impl ThumbSpec {
+    pub fn new(cfg: &Config, size: u32) -> Self {
+        Self {
+            size,
+            mode: cfg.thumbnail_mode,
+            aspect: screen_aspect(),
+        }
+    }
+
+    /// Suffix for the cache key. Empty for `Fit` so existing caches stay valid.
+    pub fn cache_tag(&self) -> String {
+        let (w, h) = self.aspect;
+        match self.mode {
+            ThumbnailMode::Fit => String::new(),
+            ThumbnailMode::Crop => format!("|crop|{w}x{h}"),
+            ThumbnailMode::Smart => format!("|smart|{w}x{h}"),
+        }
+    }
+}
+
+/// The primary monitor's resolution in pixels, falling back to 1920x1080.
+pub fn screen_resolution() -> (u32, u32) {
+    static RESOLUTION: OnceLock<(u32, u32)> = OnceLock::new();
+
+    *RESOLUTION.get_or_init(|| match screen_size::get_primary_screen_size() {
+        Ok((w, h)) if w > 0 && h > 0 => (w as u32, h as u32),
+        _ => (1920, 1080),
+    })
+}
+
+/// The primary monitor's aspect ratio, reduced (e.g. 2560x1440 -> 16x9).
+pub fn screen_aspect() -> (u32, u32) {
+    let (w, h) = screen_resolution();
+    let g = gcd(w, h);
+    (w / g, h / g)
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a.max(1) } else { gcd(b, a % b) }
+}
+
+/// Human-readable aspect ratio: reduced when that stays small ("16:9"), decimal otherwise.
+pub fn aspect_label(w: u32, h: u32) -> String {
+    let g = gcd(w, h);
+    let (a, b) = (w / g, h / g);
+    if a <= 32 && b <= 32 {
+        format!("{a}:{b}")
+    } else {
+        format!("{:.2}:1", w as f32 / h.max(1) as f32)
+    }
+}
+
+/// The `count` most common colours, found by bucketing a downscaled copy into 4 bits per
+/// channel and averaging each of the most populated buckets. Near-identical buckets are skipped
+/// so a gradient doesn't fill every slot.
+pub fn dominant_colors(img: &RgbaImage, count: usize) -> Vec<[u8; 3]> {
+    let small = image::imageops::thumbnail(img, 64, 64);
+
+    let mut buckets: std::collections::HashMap<u16, (u32, [u32; 3])> = std::collections::HashMap::new();
+    for p in small.pixels() {
+        if p[3] < 128 {
+            continue;
+        }
+        let key = ((p[0] as u16 >> 4) << 8) | ((p[1] as u16 >> 4) << 4) | (p[2] as u16 >> 4);
+        let e = buckets.entry(key).or_default();
+        e.0 += 1;
+        for c in 0..3 {
+            e.1[c] += p[c] as u32;
+        }
+    }
+
+    let mut sorted: Vec<(u32, [u32; 3])> = buckets.into_values().collect();
+    sorted.sort_by_key(|b| std::cmp::Reverse(b.0));
+
+    let mut colors: Vec<[u8; 3]> = Vec::new();
+    for (n, sum) in sorted {
+        let c = [(sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8];
+        let distinct = colors.iter().all(|o| {
+            (0..3).map(|i| (o[i] as i32 - c[i] as i32).abs()).sum::<i32>() > 48
+        });
+        if distinct {
+            colors.push(c);
+            if colors.len() == count {
+                break;
+            }
+        }
+    }
+
+    colors
+}
+
+pub fn make_thumbnail(img: &DynamicImage, spec: ThumbSpec) -> RgbaImage {
+    if spec.mode == ThumbnailMode::Fit {
+        return img.thumbnail(spec.size, spec.size).to_rgba8();
+    }
+
+    let (aw, ah) = (spec.aspect.0 as u64, spec.aspect.1 as u64);
+    let (w, h) = img.dimensions();
+
+    // Largest aw:ah window that fits inside the image.
+    let (cw, ch) = if w as u64 * ah > h as u64 * aw {
+        (((h as u64 * aw) / ah).max(1) as u32, h)
+    } else {
+        (w, ((w as u64 * ah) / aw).max(1) as u32)
+    };
+
+    let (x, y) = match spec.mode {
+        ThumbnailMode::Smart => smart_crop_offset(img, cw, ch),
+        _ => ((w - cw) / 2, (h - ch) / 2),
+    };
+
+    let size = spec.size as u64;
+    let (tw, th) = if aw >= ah {
+        (size, (size * ah / aw).max(1))
+    } else {
+        ((size * aw / ah).max(1), size)
+    };
+
+    img.crop_imm(x, y, cw, ch)
+        .thumbnail_exact(tw as u32, th as u32)
+        .to_rgba8()
+}
+
+/// Picks the crop window with the most edge detail along the axis that gets cropped,
+/// measured on a small greyscale copy of the image.
+fn smart_crop_offset(img: &DynamicImage, cw: u32, ch: u32) -> (u32, u32) {
+    let (w, h) = img.dimensions();
+    let horizontal = cw < w;
+    if !horizontal && ch >= h {
+        return (0, 0);
+    }
+
+    let small = img.thumbnail(256, 256).to_luma8();
+    let (sw, sh) = small.dimensions();
+    if sw < 2 || sh < 2 {
+        return ((w - cw) / 2, (h - ch) / 2);
+    }
+
+    let len = if horizontal { sw } else { sh } as usize;
+    let mut energy = vec![0u64; len];
+    for y in 0..sh - 1 {
+        for x in 0..sw - 1 {
+            let p = small.get_pixel(x, y)[0] as i32;
+            let dx = (small.get_pixel(x + 1, y)[0] as i32 - p).unsigned_abs();
+            let dy = (small.get_pixel(x, y + 1)[0] as i32 - p).unsigned_abs();
+            let i = if horizontal { x } else { y } as usize;
+            energy[i] += (dx + dy) as u64;
+        }
+    }
+
+    let (full, crop) = if horizontal { (w, cw) } else { (h, ch) };
+    let window = ((crop as u64 * len as u64) / full as u64).clamp(1, len as u64) as usize;
+
+    let mut sum: u64 = energy[..window].iter().sum();
+    let (mut best, mut best_start) = (sum, 0usize);
+    for start in 1..=len - window {
+        sum = sum + energy[start + window - 1] - energy[start - 1];
+        if sum > best {
+            best = sum;
+            best_start = start;
+        }
+    }
+
+    let offset = ((best_start as u64 * full as u64) / len as u64).min((full - crop) as u64) as u32;
+    if horizontal {
+        (offset, (h - ch) / 2)
+    } else {
+        ((w - cw) / 2, offset)
+    }
+}
+
+/// Renders `img` onto a `screen`-sized canvas the way `placement` describes, for backends that
+/// can't place the image themselves.
+pub fn render_for_screen(img: &DynamicImage, screen: (u32, u32), placement: Placement) -> RgbaImage {
+    use image::imageops::{FilterType, overlay};
+
+    let (sw, sh) = screen;
+    let [r, g, b] = placement.background;
+    let mut canvas = RgbaImage::from_pixel(sw, sh, image::Rgba([r, g, b, 255]));
+    let centred = |w: u32, h: u32| ((sw as i64 - w as i64) / 2, (sh as i64 - h as i64) / 2);
+
+    match placement.mode {
+        FillMode::Fill => {
+            let scaled = img.resize_to_fill(sw, sh, FilterType::Lanczos3).to_rgba8();
+            let (x, y) = centred(scaled.width(), scaled.height());
+            overlay(&mut canvas, &scaled, x, y);
+        }
+        FillMode::Fit => {
+            let scaled = img.resize(sw, sh, FilterType::Lanczos3).to_rgba8();
+            let (x, y) = centred(scaled.width(), scaled.height());
+            overlay(&mut canvas, &scaled, x, y);
+        }
+        FillMode::Center => {
+            let (x, y) = centred(img.width(), img.height());
+            overlay(&mut canvas, &img.to_rgba8(), x, y);
+        }
+        FillMode::Stretch => {
+            let scaled = img.resize_exact(sw, sh, FilterType::Lanczos3).to_rgba8();
+            overlay(&mut canvas, &scaled, 0, 0);
+        }
+        FillMode::Tile => {
+            let tile = img.to_rgba8();
+            let (tw, th) = (tile.width().max(1), tile.height().max(1));
+            for y in (0..sh).step_by(th as usize) {
+                for x in (0..sw).step_by(tw as usize) {
+                    overlay(&mut canvas, &tile, x as i64, y as i64);
                 }
             }
         }
+    }
+
+    canvas
+}
+
+pub async fn load_thumb(path: PathBuf, spec: ThumbSpec) -> Option<IcedImageHandle> {
+    tokio::task::spawn_blocking(move || {
+        if let Some(rgba) = crate::cache::read_thumb(&path, spec) {
+            let (w, h) = rgba.dimensions();
+            return Some(IcedImageHandle::from_rgba(w, h, rgba.into_raw()));
+        }
 
         match image::open(&path) {
             Ok(img) => {
-                let thumb = img.thumbnail(THUMB_SIZE, THUMB_SIZE).to_rgba8();
-
-                if let Some(cache) = crate::cache::cached_thumb_path(&path, THUMB_SIZE) {
-                    if let Some(parent) = cache.parent() {
-                        let _ = fs::create_dir_all(parent);
-                    }
-                    let dynimg = image::DynamicImage::ImageRgba8(thumb.clone());
-                    let _ = dynimg.save(&cache);
-                }
+                let thumb = make_thumbnail(&img, spec);
+                let _ = crate::cache::write_thumb(&path, spec, &thumb);
 
                 let (w, h) = thumb.dimensions();
                 let bytes = thumb.into_raw();
@@ -41,37 +268,15 @@ pub async fn load_thumb(path: PathBuf) -> Option<IcedImageHandle> {
     }).await.unwrap_or_else(|_| None)
 }
 
-pub fn ensure_thumb_cached<P: AsRef<Path>>(path: P, size: u32) -> io::Result<bool> {
+pub fn ensure_thumb_cached<P: AsRef<Path>>(path: P, spec: ThumbSpec) -> io::Result<bool> {
     let path = path.as_ref();
-    let Some(cache_path) = crate::cache::cached_thumb_path(path, size) else {
+    if crate::cache::has_thumb(path, spec) {
         return Ok(false);
-    };
-
-    if cache_path.exists() {
-        return Ok(false);
-    }
-
-    let img = image::open(path).map_err(|e| {
-        io::Error::new(io::ErrorKind::Other, e)
-    });
-    let thumb = img?.thumbnail(size, size).to_rgba8();
-
-    if let Some(parent) = cache_path.parent() {
-        fs::create_dir_all(parent)?;
     }
 
-    let nanos = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_nanos())
-        .unwrap_or(0);
-
-    let tmp_path = cache_path.with_extension(format!("{}.png", nanos));
-
-    image::DynamicImage::ImageRgba8(thumb)
-        .save(&tmp_path)
-        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-
-    fs::rename(&tmp_path, &cache_path)?;
+    let img = image::open(path).map_err(io::Error::other)?;
+    let thumb = make_thumbnail(&img, spec);
+    crate::cache::write_thumb(path, spec, &thumb)?;
 
     Ok(true)
-}
\ No newline at end of file
+}
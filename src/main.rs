@@ -12,11 +12,18 @@ use std::path::PathBuf;
 
 enum Mode {
     Ui,
-    Preview(PathBuf),
-    Random,
+    /// The image to show, plus the list to browse (read from stdin with `--browse`).
+    Preview(PathBuf, bool),
+    /// Random Wallhaven wallpaper, searched with the config plus any `--query`-style overrides.
+    Random(Box<crate::config::Config>),
     Clean,
-    Generate,
-    Dedupe,
+    /// Fetch (unless already present) and set one Wallhaven wallpaper.
+    WallhavenId(String),
+    /// Mirror a Wallhaven collection (user, collection, prune) into a local folder.
+    WallhavenSync(String, String, bool),
+    CacheStats,
+    Generate(Vec<u32>),
+    Dedupe(crate::commands::dedupe::DedupeAction),
     #[cfg(target_os = "macos")]
     Tray,
 }
@@ -25,6 +32,11 @@ fn parse_args() -> Result<Mode, String> {
     let mut args = std::env::args().skip(1);
 
     let mut selected: Option<Mode> = None;
+    let mut link: Option<crate::commands::dedupe::DedupeAction> = None;
+    let mut sizes: Vec<u32> = Vec::new();
+    let mut browse = false;
+    let mut prune = false;
+    let mut search: Vec<(String, String)> = Vec::new();
 
     while let Some(arg) = args.next() {
         let next_mode = match arg.as_str() {
@@ -32,16 +44,88 @@ fn parse_args() -> Result<Mode, String> {
                 let p = args
                     .next()
                     .ok_or_else(|| "Missing value after --preview".to_string())?;
-                Mode::Preview(PathBuf::from(p))
+                Mode::Preview(PathBuf::from(p), false)
             }
             #[cfg(target_os = "macos")]
             "--ui" => Mode::Ui,
-            "--random" => Mode::Random,
+            // The config is loaded and the search flags applied once all arguments are read.
+            "--random" => Mode::Random(Box::default()),
+            flag if crate::wallhaven::SEARCH_FLAGS.contains(&flag) => {
+                let v = args
+                    .next()
+                    .ok_or_else(|| format!("Missing value after {flag}"))?;
+                search.push((flag.to_string(), v));
+                continue;
+            }
             "--clean" => Mode::Clean,
-            "--generate" => Mode::Generate,
-            "--dedupe" => Mode::Dedupe,
+            "--wallhaven-id" => {
+                let id = args
+                    .next()
+                    .ok_or_else(|| "Missing value after --wallhaven-id".to_string())?;
+                if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(format!("Invalid --wallhaven-id value: {id}"));
+                }
+                Mode::WallhavenId(id)
+            }
+            "--wallhaven-sync" => {
+                let v = args
+                    .next()
+                    .ok_or_else(|| "Missing value after --wallhaven-sync".to_string())?;
+                match v.split_once('/') {
+                    Some((user, collection)) if !user.is_empty() && !collection.is_empty() => {
+                        Mode::WallhavenSync(user.to_string(), collection.to_string(), false)
+                    }
+                    _ => return Err(format!("Invalid --wallhaven-sync value: {v} (expected <user>/<collection>)")),
+                }
+            }
+            "--prune" => {
+                prune = true;
+                continue;
+            }
+            "--cache-stats" => Mode::CacheStats,
+            "--generate" => Mode::Generate(Vec::new()),
+            "--size" => {
+                let v = args
+                    .next()
+                    .ok_or_else(|| "Missing value after --size".to_string())?;
+                for part in v.split(',') {
+                    // Other sizes would never be shown by the grid and `--clean` would drop them.
+                    let size = part
+                        .trim()
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|s| crate::image::zoom_sizes().any(|z| z == *s))
+                        .ok_or_else(|| {
+                            format!(
+                                "Invalid --size value: {part} (expected one of {}, {}, …, {})",
+                                crate::image::MIN_THUMB_SIZE,
+                                crate::image::MIN_THUMB_SIZE + crate::image::ZOOM_STEP,
+                                crate::image::MAX_THUMB_SIZE
+                            )
+                        })?;
+                    sizes.push(size);
+                }
+                continue;
+            }
+            "--browse" => {
+                browse = true;
+                continue;
+            }
+            "--dedupe" => Mode::Dedupe(Default::default()),
+            "--link" => {
+                let v = args
+                    .next()
+                    .ok_or_else(|| "Missing value after --link".to_string())?;
+                link = Some(
+                    crate::commands::dedupe::DedupeAction::parse(&v)
+                        .ok_or_else(|| format!("Invalid --link value: {v} (expected hard or sym)"))?,
+                );
+                continue;
+            }
             "--help" | "-h" => {
-                return Err("Usage:\n  wallpicker [--ui | --preview <path> | --random | --clean | --generate | --dedupe]\n".to_string());
+                // Asked for, so not a failure.
+                eprint!("Usage:\n  wallpicker [--ui | --preview <path> [--browse] | --random [--query <q>] [--categories <111>] [--purity <100>] [--atleast <WxH>] [--resolutions <WxH,...>] [--ratios <landscape|16x9,...>] [--colors <hex>] [--sorting <s>] [--order <desc|asc>] [--top-range <1M>] [--seed <abc123>] | --wallhaven-id <id> | --wallhaven-sync <user>/<collection> [--prune] | --clean | --cache-stats | --generate [--size <px>[,<px>...]] | --dedupe [--link hard|sym]]\n");
+                std::process::exit(0);
             }
             _ => {
                 return Err(format!("Unknown argument: {arg}"));
@@ -55,6 +139,53 @@ fn parse_args() -> Result<Mode, String> {
         selected = Some(next_mode);
     }
 
+    if !sizes.is_empty() {
+        match selected {
+            Some(Mode::Generate(_)) => selected = Some(Mode::Generate(sizes)),
+            _ => return Err("--size can only be used with --generate".to_string()),
+        }
+    }
+
+    if browse {
+        match selected {
+            Some(Mode::Preview(p, _)) => selected = Some(Mode::Preview(p, true)),
+            _ => return Err("--browse can only be used with --preview".to_string()),
+        }
+    }
+
+    if prune {
+        match selected {
+            Some(Mode::WallhavenSync(user, collection, _)) => {
+                selected = Some(Mode::WallhavenSync(user, collection, true))
+            }
+            _ => return Err("--prune can only be used with --wallhaven-sync".to_string()),
+        }
+    }
+
+    match selected {
+        Some(Mode::Random(_)) => {
+            let mut cfg = crate::config::load_or_create_config();
+            for (flag, value) in search {
+                crate::wallhaven::apply_search_flag(&mut cfg, &flag, value)?;
+            }
+            crate::wallhaven::SearchParams::from_config(&cfg)
+                .validate()
+                .map_err(|e| format!("Invalid Wallhaven search: {e}"))?;
+            selected = Some(Mode::Random(Box::new(cfg)));
+        }
+        _ if !search.is_empty() => {
+            return Err(format!("{} can only be used with --random", search[0].0));
+        }
+        _ => {}
+    }
+
+    if let Some(action) = link {
+        match selected {
+            Some(Mode::Dedupe(_)) => selected = Some(Mode::Dedupe(action)),
+            _ => return Err("--link can only be used with --dedupe".to_string()),
+        }
+    }
+
     #[cfg(target_os = "macos")]
     return Ok(selected.unwrap_or(Mode::Tray));
     #[cfg(not(target_os = "macos"))]
@@ -102,15 +233,64 @@ fn main() -> iced::Result {
         Ok(m) => m,
         Err(msg) => {
             eprintln!("{msg}");
-            return Ok(());
+            std::process::exit(2);
         }
     };
 
     match mode {
-        Mode::Random => {
+        Mode::Random(cfg) => {
+            run_async(async move {
+                match crate::commands::set_random_wallpaper_via_wallhaven(*cfg).await {
+                    Ok(path) => println!("{}", path.display()),
+                    Err(e) => {
+                        eprintln!("Failed to set a Wallhaven wallpaper: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            });
+            Ok(())
+        }
+        Mode::WallhavenId(id) => {
             let cfg = crate::config::load_or_create_config();
             run_async(async move {
-                let _ = crate::commands::set_random_wallpaper_via_wallhaven(cfg).await;
+                match crate::wallhaven::fetch_by_id(&cfg, &id).await {
+                    Ok(path) => {
+                        println!("{}", path.display());
+                        crate::commands::set_wallpaper(path, cfg).await;
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to fetch Wallhaven wallpaper {id}: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            });
+            Ok(())
+        }
+        Mode::WallhavenSync(user, collection, prune) => {
+            let cfg = crate::config::load_or_create_config();
+            run_async(async move {
+                match crate::wallhaven::sync_collection(&cfg, &user, &collection, prune).await {
+                    Ok(report) => {
+                        for (id, e) in &report.failed {
+                            eprintln!("Failed to download {id}: {e}");
+                        }
+                        println!(
+                            "Synced {user}/{collection} into {}: {} downloaded, {} already present, {} removed, {} failed",
+                            report.folder.display(),
+                            report.downloaded,
+                            report.present,
+                            report.removed,
+                            report.failed.len()
+                        );
+                        if !report.failed.is_empty() {
+                            std::process::exit(1);
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to sync {user}/{collection}: {e}");
+                        std::process::exit(1);
+                    }
+                }
             });
             Ok(())
         }
@@ -118,54 +298,85 @@ fn main() -> iced::Result {
             let cfg = crate::config::load_or_create_config();
             run_async(async move {
                 let imgs = crate::scanner::scan_directories(cfg.folders.clone()).await;
-                let removed = crate::cache::clean_orphan_thumbnails_for_images(
-                    &imgs,
-                    crate::image::THUMB_SIZE,
-                )
+                let specs: Vec<crate::image::ThumbSpec> = crate::image::zoom_sizes()
+                    .chain(std::iter::once(cfg.thumbnail_size))
+                    .map(|size| crate::image::ThumbSpec::new(&cfg, size))
+                    .collect();
+                let removed = crate::cache::clean_orphan_thumbnails_for_images(&imgs, &specs)
                     .unwrap_or(0);
                 println!("Removed {} orphaned thumbnail(s)", removed);
             });
             Ok(())
         }
-        Mode::Generate => {
+        Mode::CacheStats => {
             let cfg = crate::config::load_or_create_config();
-            run_async(async move {
-                let imgs = crate::scanner::scan_directories(cfg.folders.clone()).await;
-
-                println!("Found {} image(s) to generate thumbnails for.", imgs.len());
-
-                let res = tokio::task::spawn_blocking(move || {
-                    let mut generated = 0usize;
-                    let mut skipped = 0usize;
-                    let mut failed = 0usize;
-
-                    for p in imgs {
-                        match crate::image::ensure_thumb_cached(&p, crate::image::THUMB_SIZE) {
-                            Ok(true) => generated += 1,
-                            Ok(false) => skipped += 1,
-                            Err(_) => failed += 1,
-                        }
-                    }
-
-                    (generated, skipped, failed)
-                })
-                    .await
-                    .unwrap_or((0, 0, 0));
+            let usage = crate::cache::usage();
+            let stats = crate::cache::load_stats();
+            let fmt_limit = |n: u64, unit: &str| {
+                if n == 0 { String::from("unlimited") } else { format!("{n}{unit}") }
+            };
 
+            println!("Cache directory: {}", crate::cache::cache_dir().display());
+            println!(
+                "Entries: {} (limit {})",
+                usage.entries,
+                fmt_limit(cfg.cache_max_entries as u64, "")
+            );
+            println!(
+                "Size: {} (limit {})",
+                crate::commands::dedupe::format_size(usage.bytes),
+                fmt_limit(cfg.cache_max_mb, " MiB")
+            );
+            if let Some(pack) = usage.pack_bytes {
                 println!(
-                    "Generated {} thumbnail(s); {} already cached; {} failed",
-                    res.0, res.1, res.2
+                    "Pack file: {} ({} reclaimable)",
+                    crate::commands::dedupe::format_size(pack),
+                    crate::commands::dedupe::format_size(pack.saturating_sub(usage.bytes))
                 );
+            }
+            match stats.hit_rate() {
+                Some(rate) => println!(
+                    "Hit rate: {:.1}% ({} hit(s), {} miss(es))",
+                    rate * 100.0,
+                    stats.hits,
+                    stats.misses
+                ),
+                None => println!("Hit rate: no lookups recorded yet"),
+            }
+            println!(
+                "Least recently used: {}",
+                crate::commands::dedupe::format_time(usage.oldest_access)
+            );
+            println!(
+                "Most recently used: {}",
+                crate::commands::dedupe::format_time(usage.newest_access)
+            );
+            Ok(())
+        }
+        Mode::Generate(sizes) => {
+            let cfg = crate::config::load_or_create_config();
+            run_async(async move {
+                crate::commands::run_generate(cfg, sizes).await;
             });
             Ok(())
         }
-        Mode::Preview(p) => {
-            ui::preview::run(p)
+        Mode::Preview(p, browse) => {
+            let list: Vec<PathBuf> = if browse {
+                std::io::stdin()
+                    .lines()
+                    .map_while(Result::ok)
+                    .filter(|l| !l.is_empty())
+                    .map(PathBuf::from)
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            ui::preview::run(p, list)
         }
-        Mode::Dedupe => {
+        Mode::Dedupe(action) => {
             let cfg = crate::config::load_or_create_config();
             run_async(async move {
-                crate::commands::run_dedupe(cfg).await;
+                crate::commands::run_dedupe(cfg, action).await;
             });
             Ok(())
         }
@@ -16,6 +16,11 @@ pub fn set_wallpaper(path: &Path) -> io::Result<ExitStatus> {
         .status()
 }
 
+/// Opens `url` in the default browser.
+pub fn open_url(url: &str) -> io::Result<()> {
+    std::process::Command::new("open").arg(url).spawn().map(|_| ())
+}
+
 pub fn window_settings(_app_id: &str) -> PlatformSpecific {
     PlatformSpecific::default()
 }
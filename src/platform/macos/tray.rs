@@ -154,8 +154,8 @@ pub fn run(cfg: Config) {
 
                 rt.spawn(async move {
                     match crate::commands::set_random_wallpaper_via_wallhaven(cfg).await {
-                        Some(p) => println!("Set wallpaper: {}", p.display()),
-                        None => eprintln!("Failed to set random WallHaven wallpaper"),
+                        Ok(p) => println!("Set wallpaper: {}", p.display()),
+                        Err(e) => eprintln!("Failed to set random WallHaven wallpaper: {e}"),
                     }
                 });
             }
@@ -1,9 +1,9 @@
 #[cfg(target_os = "macos")]
 pub mod macos;
 #[cfg(target_os = "macos")]
-pub use macos::{set_wallpaper, window_settings};
+pub use macos::{open_url, set_wallpaper, window_settings};
 
 #[cfg(target_os = "linux")]
 pub mod linux;
 #[cfg(target_os = "linux")]
-pub use linux::{set_wallpaper, window_settings};
+pub use linux::{open_url, set_wallpaper, window_settings};
@@ -1,4 +1,5 @@
 use std::collections::HashSet;
+use std::os::unix::fs::MetadataExt;
 use std::path::PathBuf;
 use std::time::SystemTime;
 use walkdir::WalkDir;
@@ -7,18 +8,21 @@ pub async fn scan_directories(dirs: Vec<PathBuf>) -> Vec<PathBuf> {
     tokio::task::spawn_blocking(move || {
         let mut files: Vec<PathBuf> = Vec::new();
         let mut seen: HashSet<PathBuf> = HashSet::new();
+        // Hardlinked copies share an inode; only the first path found is listed.
+        let mut seen_inodes: HashSet<(u64, u64)> = HashSet::new();
         for dir in dirs {
             if !dir.exists() {
                 continue;
             }
             for entry in WalkDir::new(dir).into_iter().filter_map(Result::ok) {
-                if entry.metadata().map(|m| m.is_file()).unwrap_or(false) {
+                let Ok(meta) = entry.metadata() else { continue };
+                if meta.is_file() {
                     let path = entry.path();
                     if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
                         let ext = ext.to_lowercase();
                         if ext == "png" || ext == "jpg" || ext == "jpeg" || ext == "webp" {
                             let pb = path.to_path_buf();
-                            if seen.insert(pb.clone()) {
+                            if seen.insert(pb.clone()) && seen_inodes.insert((meta.dev(), meta.ino())) {
                                 files.push(pb);
                             }
                         }
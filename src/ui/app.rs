@@ -3,17 +3,21 @@ use iced::keyboard::{self, Key, key::Named};
 use iced::widget::image::Handle as IcedImageHandle;
 use iced::widget::mouse_area;
 use iced::widget::svg::{self, Svg};
-use iced::widget::{Image, button, column, container, row, scrollable, text};
-use iced::{Color, Element, Length, Size, Task, Theme, application, window};
+use iced::widget::{Image, button, column, container, row, scrollable, stack, text, tooltip};
+use iced::{Color, ContentFit, Element, Length, Size, Task, Theme, application, window};
 
 use iced::widget::scrollable::{Direction, Scrollbar};
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
-use crate::config::Config;
-use crate::image::{THUMB_SIZE, load_thumb};
+use crate::commands::PreviewEvent;
+use crate::config::{Config, TileAspect};
+use crate::image::{DEFAULT_THUMB_SIZE, ThumbSpec, load_thumb, zoom_in, zoom_out};
 use crate::scanner::scan_directories;
-use crate::ui::icons::{DICE_SVG, WALLHAVEN_SVG};
+use crate::ui::dedupe::{self, DedupeState, DuplicateGroupView};
+use crate::ui::wallhaven::{self as wallhaven_ui, WallhavenState};
+use crate::wallhaven::{SearchPage, Wallpaper};
+use crate::ui::icons::{DICE_SVG, DUPLICATES_SVG, WALLHAVEN_SVG};
 use super::platform_specific_settings;
 
 pub fn run(window_width: u32, window_height: u32) -> iced::Result {
@@ -30,14 +34,26 @@ pub fn run(window_width: u32, window_height: u32) -> iced::Result {
             platform_specific: platform_specific_settings("wallpicker-main"),
             ..Default::default()
         })
-        .subscription(|_state| iced::event::listen().map(Message::EventOccurred))
+        .subscription(|_state| {
+            // Wheel events over the grid are captured by the scrollable, so listen to
+            // everything rather than just the ignored events to catch Ctrl+scroll.
+            iced::event::listen_with(|event, _status, _id| Some(Message::EventOccurred(event)))
+        })
         .run_with(move || {
             let mut s = WallPicker::default();
             let cfg = crate::config::load_or_create_config();
+            s.thumb_size = cfg.thumbnail_size;
             s.config = cfg;
             s.window_width = window_width;
             s.window_height = window_height;
-            (s, Task::perform(async {}, |_| Message::ScanDirectory))
+
+            let prune = Task::future(crate::cache::prune_cache(
+                s.config.cache_max_mb,
+                s.config.cache_max_entries,
+            ))
+            .discard();
+
+            (s, prune.chain(Task::done(Message::ScanDirectory)))
         })
 }
 
@@ -46,23 +62,45 @@ struct WallPicker {
     paths: Vec<PathBuf>,
     thumbs: HashMap<PathBuf, IcedImageHandle>,
     loading: HashSet<PathBuf>,
+    thumb_size: u32,
+    modifiers: keyboard::Modifiers,
     selected: Option<PathBuf>,
     window_width: u32,
     window_height: u32,
     config: Config,
+    dedupe: Option<DedupeState>,
+    wallhaven: Option<WallhavenState>,
 }
 
 #[derive(Debug, Clone)]
-enum Message {
+pub(super) enum Message {
     ScanDirectory,
     Scanned(Vec<PathBuf>),
-    LoadedThumb(PathBuf, Option<IcedImageHandle>),
+    LoadedThumb(PathBuf, u32, Option<IcedImageHandle>),
+    Zoom(u32),
     SelectWallpaper(PathBuf),
     SetRandomWallpaper,
     SetWallhavenWallpaper,
+    WallhavenWallpaperSet(Result<PathBuf, String>),
     EventOccurred(Event),
     OpenPreview(PathBuf),
-    PreviewClosed(PathBuf, bool),
+    PreviewClosed(Vec<PreviewEvent>),
+    OpenDedupe,
+    DuplicatesFound(Vec<DuplicateGroupView>),
+    KeepDuplicate(usize, usize),
+    KeepNewestDuplicate(usize),
+    SkipDuplicates(usize),
+    ConfirmKeepDuplicate,
+    CancelKeepDuplicate,
+    CloseDedupe,
+    OpenWallhaven,
+    WallhavenGoto(u32),
+    WallhavenPage(Result<SearchPage, String>),
+    WallhavenThumb(String, Option<IcedImageHandle>),
+    WallhavenChoose(usize),
+    WallhavenPreview(usize),
+    WallhavenFetched(Result<PathBuf, String>, bool),
+    CloseWallhaven,
     Close
 }
 
@@ -76,60 +114,268 @@ fn update(state: &mut WallPicker, message: Message) -> Task<Message> {
         Message::Scanned(paths) => {
             state.paths = paths;
 
-            let tasks: Vec<Task<Message>> = state
+            let missing: Vec<PathBuf> = state
                 .paths
                 .iter()
                 .filter(|p| !state.thumbs.contains_key(*p))
-                .map(|p| {
-                    let p = p.clone();
-                    state.loading.insert(p.clone());
-                    Task::perform(load_thumb(p.clone()), move |h| {
-                        Message::LoadedThumb(p.clone(), h)
-                    })
-                })
+                .cloned()
                 .collect();
 
-            return Task::batch(tasks);
+            return state.load_thumbs(missing);
         }
-        Message::LoadedThumb(path, handle_opt) => {
+        Message::LoadedThumb(path, size, handle_opt) => {
+            // Results for a size we've since zoomed away from are dropped; the reload for
+            // the current size is already queued.
+            if size != state.thumb_size {
+                return Task::none();
+            }
+
             if let Some(handle) = handle_opt {
                 state.thumbs.insert(path.clone(), handle);
             }
 
             state.loading.remove(&path);
+            if state.loading.is_empty() {
+                let _ = crate::cache::flush_stats();
+            }
+        }
+        Message::Zoom(size) => {
+            if size == state.thumb_size {
+                return Task::none();
+            }
+
+            state.thumb_size = size;
+            state.config.thumbnail_size = size;
+            let _ = crate::config::save_config(&state.config);
+
+            // Keep showing the old handles scaled until the new size has loaded.
+            state.loading.clear();
+            let mut paths = state.paths.clone();
+            if let Some(d) = &state.dedupe {
+                paths.extend(d.paths().cloned());
+            }
+
+            return state.load_thumbs(paths);
         }
         Message::SelectWallpaper(path) => {
             state.selected = Some(path.clone());
 
-            let copy_to_tmp = state.config.copy_to_tmp;
-            return Task::perform(crate::commands::set_wallpaper(path, copy_to_tmp), |_| Message::Close);
+            let cfg = state.config.clone();
+            return Task::perform(crate::commands::set_wallpaper(path, cfg), |_| Message::Close);
         }
         Message::OpenPreview(p) => {
-            let p_for_process = p.clone();
-            let p_for_cb = p.clone();
+            // Browse whatever list the image was opened from.
+            let list: Vec<PathBuf> = match &state.dedupe {
+                Some(d) => d.paths().cloned().collect(),
+                None => state.paths.clone(),
+            };
+            return Task::perform(crate::commands::open_preview(p, list), Message::PreviewClosed);
+        }
+        Message::PreviewClosed(events) => {
+            // The preview may have saved per-image placement changes or blacklisted something.
+            let saved = crate::config::load_config();
+            state.config.placements = saved.placements;
+            state.config.wallhaven_blacklist = saved.wallhaven_blacklist;
+
+            let mut close = false;
+            for event in events {
+                match event {
+                    PreviewEvent::Deleted { path } => {
+                        state.forget_path(&path);
+
+                        if let Some(d) = state.dedupe.as_mut() {
+                            d.forget(&path);
+                        }
+                    }
+                    PreviewEvent::WallpaperSet { path } if path.starts_with(crate::wallhaven::preview_dir()) => {
+                        // Chosen from a Wallhaven preview: keep it in the wallpaper folder.
+                        if let Some(w) = &state.wallhaven
+                            && let Some(id) = crate::wallhaven::wallhaven_id(&path)
+                            && let Some(index) = w.find(&id)
+                        {
+                            return Task::done(Message::WallhavenChoose(index));
+                        }
+                    }
+                    PreviewEvent::WallpaperSet { path } => {
+                        state.selected = Some(path);
+                        close = true;
+                    }
+                    PreviewEvent::Moved { from, to } => {
+                        state.rename_path(&from, to);
+                    }
+                    PreviewEvent::Favourited { path, favourite } => {
+                        state.config.favourites.retain(|p| p != &path);
+                        if favourite {
+                            state.config.favourites.push(path);
+                        }
+                    }
+                }
+            }
+
+            if close {
+                return Task::done(Message::Close);
+            }
+        }
+        Message::OpenDedupe => {
+            state.dedupe = Some(DedupeState {
+                scanning: true,
+                ..Default::default()
+            });
+
+            let folders = state.config.folders.clone();
+            return Task::perform(dedupe::load_duplicate_groups(folders), Message::DuplicatesFound);
+        }
+        Message::DuplicatesFound(groups) => {
+            let Some(d) = state.dedupe.as_mut() else {
+                return Task::none();
+            };
+            d.scanning = false;
+            d.groups = groups;
+
+            let missing: Vec<PathBuf> = d
+                .paths()
+                .filter(|p| !state.thumbs.contains_key(*p) && !state.loading.contains(*p))
+                .cloned()
+                .collect();
+
+            return state.load_thumbs(missing);
+        }
+        Message::KeepDuplicate(group, keep_index) => {
+            if let Some(d) = state.dedupe.as_mut() {
+                d.ask_keep(group, keep_index);
+            }
+        }
+        Message::KeepNewestDuplicate(group) => {
+            if let Some(d) = state.dedupe.as_mut() {
+                d.ask_keep_newest(group);
+            }
+        }
+        Message::ConfirmKeepDuplicate => {
+            if let Some(d) = state.dedupe.as_mut() {
+                for p in d.confirm() {
+                    state.forget_path(&p);
+                }
+            }
+        }
+        Message::CancelKeepDuplicate => {
+            if let Some(d) = state.dedupe.as_mut() {
+                d.cancel();
+            }
+        }
+        Message::SkipDuplicates(group) => {
+            if let Some(d) = state.dedupe.as_mut() {
+                d.skip(group);
+            }
+        }
+        Message::CloseDedupe => {
+            state.dedupe = None;
+        }
+        Message::OpenWallhaven => {
+            state.wallhaven = Some(WallhavenState::default());
+            return Task::done(Message::WallhavenGoto(1));
+        }
+        Message::WallhavenGoto(page) => {
+            let Some(w) = state.wallhaven.as_mut() else {
+                return Task::none();
+            };
+            w.loading = true;
+
+            let cfg = state.config.clone();
+            let seed = w.seed.clone();
             return Task::perform(
-                crate::commands::open_preview(p_for_process),
-                move |deleted| Message::PreviewClosed(p_for_cb.clone(), deleted),
+                async move {
+                    crate::wallhaven::search(&cfg, page, seed)
+                        .await
+                        .map_err(|e| e.to_string())
+                },
+                Message::WallhavenPage,
             );
         }
-        Message::PreviewClosed(p, deleted) => {
-            if deleted {
-                state.paths.retain(|x| x != &p);
-                state.thumbs.remove(&p);
-                state.loading.remove(&p);
+        Message::WallhavenPage(result) => {
+            let Some(w) = state.wallhaven.as_mut() else {
+                return Task::none();
+            };
+            match result {
+                Ok(page) => w.show(page),
+                Err(e) => {
+                    w.loading = false;
+                    w.error = Some(e);
+                    return Task::none();
+                }
+            }
 
-                if matches!(state.selected.as_ref(), Some(sel) if sel == &p) {
-                    state.selected = None;
+            let cfg = std::sync::Arc::new(state.config.clone());
+            let tasks: Vec<Task<Message>> = w
+                .items
+                .iter()
+                .map(|wp| {
+                    let id = wp.id.clone();
+                    let url = wallhaven_ui::thumb_url(wp, state.thumb_size).to_string();
+                    let cfg = cfg.clone();
+                    Task::perform(
+                        async move { crate::wallhaven::fetch_thumbnail(&cfg, url).await },
+                        move |bytes| Message::WallhavenThumb(id.clone(), bytes.map(IcedImageHandle::from_bytes)),
+                    )
+                })
+                .collect();
+            return Task::batch(tasks);
+        }
+        Message::WallhavenThumb(id, handle) => {
+            if let Some(w) = state.wallhaven.as_mut()
+                && let Some(h) = handle
+                && w.find(&id).is_some()
+            {
+                w.thumbs.insert(id, h);
+            }
+        }
+        Message::WallhavenChoose(index) => {
+            return state.fetch_wallhaven(index, false);
+        }
+        Message::WallhavenPreview(index) => {
+            return state.fetch_wallhaven(index, true);
+        }
+        Message::WallhavenFetched(result, preview) => {
+            let Some(w) = state.wallhaven.as_mut() else {
+                return Task::none();
+            };
+            w.fetching = None;
+            match result {
+                Ok(path) if preview => {
+                    return Task::perform(crate::commands::open_preview(path, Vec::new()), Message::PreviewClosed);
                 }
+                Ok(path) => return Task::done(Message::SelectWallpaper(path)),
+                Err(e) => w.error = Some(e),
             }
         }
+        Message::CloseWallhaven => {
+            state.wallhaven = None;
+            return Task::future(tokio::task::spawn_blocking(crate::wallhaven::clear_previews)).discard();
+        }
         Message::SetWallhavenWallpaper => {
+            if let Some(w) = state.wallhaven.as_mut() {
+                w.error = None;
+                w.fetching = Some(String::from("a random wallpaper"));
+            }
             let cfg = state.config.clone();
             return Task::perform(
-                crate::commands::set_random_wallpaper_via_wallhaven(cfg),
-                |_| Message::Close,
+                async move {
+                    crate::commands::set_random_wallpaper_via_wallhaven(cfg)
+                        .await
+                        .map_err(|e| e.to_string())
+                },
+                Message::WallhavenWallpaperSet,
             );
         }
+        Message::WallhavenWallpaperSet(result) => match result {
+            Ok(_) => return Task::done(Message::Close),
+            // Stay open so the user can see what went wrong and try something else.
+            Err(e) => {
+                if let Some(w) = state.wallhaven.as_mut() {
+                    w.fetching = None;
+                    w.error = Some(e);
+                }
+            }
+        },
         Message::SetRandomWallpaper => {
             let cfg = state.config.clone();
             return Task::perform(
@@ -147,8 +393,46 @@ fn update(state: &mut WallPicker, message: Message) -> Task<Message> {
                 state.window_width = size.width as u32;
                 state.window_height = size.height as u32;
             }
-            Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) => {
-                if key == Key::Named(Named::Escape) {
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                state.modifiers = modifiers;
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: Key::Character(c),
+                modifiers,
+                ..
+            }) if modifiers.command() => {
+                let size = match c.as_str() {
+                    "+" | "=" => zoom_in(state.thumb_size),
+                    "-" => zoom_out(state.thumb_size),
+                    "0" => DEFAULT_THUMB_SIZE,
+                    _ => return Task::none(),
+                };
+                return Task::done(Message::Zoom(size));
+            }
+            Event::Mouse(iced::mouse::Event::WheelScrolled { delta }) if state.modifiers.command() => {
+                let y = match delta {
+                    iced::mouse::ScrollDelta::Lines { y, .. } => y,
+                    iced::mouse::ScrollDelta::Pixels { y, .. } => y,
+                };
+                if y > 0.0 {
+                    return Task::done(Message::Zoom(zoom_in(state.thumb_size)));
+                } else if y < 0.0 {
+                    return Task::done(Message::Zoom(zoom_out(state.thumb_size)));
+                }
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: Key::Named(Named::Escape),
+                ..
+            }) => {
+                if let Some(d) = state.dedupe.as_mut()
+                    && d.pending.is_some()
+                {
+                    d.cancel();
+                } else if state.dedupe.is_some() {
+                    state.dedupe = None;
+                } else if state.wallhaven.is_some() {
+                    return Task::done(Message::CloseWallhaven);
+                } else {
                     std::process::exit(0);
                 }
             }
@@ -159,147 +443,248 @@ fn update(state: &mut WallPicker, message: Message) -> Task<Message> {
 }
 
 fn view(state: &WallPicker) -> Element<'_, Message> {
-    let cols = state.suggested_columns();
+    if let Some(d) = &state.dedupe {
+        return dedupe::view(d, &state.thumbs, state.thumb_size);
+    }
+
+    if let Some(w) = &state.wallhaven {
+        let tiles = w
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, wp)| state.remote_thumbnail_widget(i, wp, w))
+            .collect();
+        return wallhaven_ui::view(w, state.grid(tiles));
+    }
+
     let mut tiles: Vec<Element<Message>> = Vec::new();
 
     tiles.push(state.random_widget());
     tiles.push(state.wallhaven_widget());
+    tiles.push(state.duplicates_widget());
 
     for p in state.paths.iter() {
         tiles.push(state.thumbnail_widget(p));
     }
 
-    let mut rows_ui: Vec<Element<Message>> = Vec::new();
-    let mut it = tiles.into_iter();
-    let cols = cols.max(1);
-
-    loop {
-        let Some(first) = it.next() else { break };
-        let mut r = row![];
+    container(state.grid(tiles))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding([state.config.tile_padding, 0])
+        .into()
+}
 
-        r = r.push(first);
-        for _ in 1..cols {
-            if let Some(elem) = it.next() {
-                r = r.push(elem);
-            } else {
-                break;
+impl WallPicker {
+    /// Lays `tiles` out in rows that fit the window, inside a vertical scrollable.
+    fn grid<'a>(&self, tiles: Vec<Element<'a, Message>>) -> Element<'a, Message> {
+        let cols = self.suggested_columns().max(1);
+        let mut rows_ui: Vec<Element<Message>> = Vec::new();
+        let mut it = tiles.into_iter();
+
+        loop {
+            let Some(first) = it.next() else { break };
+            let mut r = row![].spacing(self.config.tile_spacing);
+
+            r = r.push(first);
+            for _ in 1..cols {
+                if let Some(elem) = it.next() {
+                    r = r.push(elem);
+                } else {
+                    break;
+                }
             }
+            rows_ui.push(r.into());
         }
-        rows_ui.push(r.into());
+
+        let grid = column(rows_ui).spacing(self.config.tile_spacing);
+        scrollable(container(grid).width(Length::Fill))
+            .direction(Direction::Vertical(Scrollbar::default().width(0).scroller_width(0).margin(0)))
+            .height(Length::Fill)
+            .into()
     }
 
-    let grid = column(rows_ui).spacing(1);
-    let scroll = scrollable(container(grid).width(Length::Fill))
-        .direction(Direction::Vertical(Scrollbar::default().width(0).scroller_width(0).margin(0)))
-        .height(Length::Fill);
+    fn fetch_wallhaven(&mut self, index: usize, preview: bool) -> Task<Message> {
+        let Some(w) = self.wallhaven.as_mut() else {
+            return Task::none();
+        };
+        let Some(wp) = w.items.get(index).cloned() else {
+            return Task::none();
+        };
+        w.error = None;
+        w.fetching = Some(wp.id.clone());
+
+        let cfg = self.config.clone();
+        Task::perform(
+            async move {
+                let res = if preview {
+                    crate::wallhaven::download_preview(&cfg, &wp).await
+                } else {
+                    crate::wallhaven::download(&cfg, &wp).await
+                };
+                res.map_err(|e| e.to_string())
+            },
+            move |res| Message::WallhavenFetched(res, preview),
+        )
+    }
 
-    container(scroll)
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .padding([10,0])
-        .into()
-}
+    fn tile_size(&self) -> (f32, f32) {
+        let w = self.thumb_size as f32;
+        match self.config.tile_aspect {
+            TileAspect::Square | TileAspect::Native => (w, w),
+            TileAspect::Wide => (w, (w * 9.0 / 16.0).round()),
+        }
+    }
 
-impl WallPicker {
     fn suggested_columns(&self) -> usize {
-        let total = self.window_width.max(1) as i32;
-        let cols = (total / THUMB_SIZE as i32).max(1) as usize;
-        cols
+        let spacing = self.config.tile_spacing as i32;
+        let total = self.window_width.max(1) as i32 + spacing;
+        (total / (self.thumb_size as i32 + spacing)).max(1) as usize
+    }
+
+    fn load_thumbs(&mut self, paths: Vec<PathBuf>) -> Task<Message> {
+        let size = self.thumb_size;
+        let spec = ThumbSpec::new(&self.config, size);
+        let tasks: Vec<Task<Message>> = paths
+            .into_iter()
+            .map(|p| {
+                self.loading.insert(p.clone());
+                Task::perform(load_thumb(p.clone(), spec), move |h| {
+                    Message::LoadedThumb(p.clone(), size, h)
+                })
+            })
+            .collect();
+
+        Task::batch(tasks)
+    }
+
+    fn forget_path(&mut self, p: &PathBuf) {
+        self.paths.retain(|x| x != p);
+        self.thumbs.remove(p);
+        self.loading.remove(p);
+
+        if matches!(self.selected.as_ref(), Some(sel) if sel == p) {
+            self.selected = None;
+        }
+    }
+
+    fn rename_path(&mut self, from: &PathBuf, to: PathBuf) {
+        self.config.rename_path(from, &to);
+        if let Some(h) = self.thumbs.remove(from) {
+            self.thumbs.insert(to.clone(), h);
+        }
+        if let Some(d) = self.dedupe.as_mut() {
+            d.rename(from, &to);
+        }
+        if let Some(p) = self.paths.iter_mut().find(|p| *p == from) {
+            *p = to;
+        }
     }
 
     fn thumbnail_widget(&self, path: &PathBuf) -> Element<'_, Message> {
+        let (w, h) = self.tile_size();
+        let fit = match self.config.tile_aspect {
+            TileAspect::Square | TileAspect::Wide => ContentFit::Cover,
+            TileAspect::Native => ContentFit::Contain,
+        };
+
         let base: Element<Message> = if let Some(handle) = self.thumbs.get(path) {
             Image::new(handle.clone())
-                .width(Length::Fixed(THUMB_SIZE as f32))
-                .height(Length::Fixed(THUMB_SIZE as f32))
+                .content_fit(fit)
+                .width(Length::Fixed(w))
+                .height(Length::Fixed(h))
                 .into()
         } else {
             container(text(" "))
-                .width(Length::Fixed(THUMB_SIZE as f32))
-                .height(Length::Fixed(THUMB_SIZE as f32))
+                .width(Length::Fixed(w))
+                .height(Length::Fixed(h))
                 .into()
         };
 
-        let tile = container(base)
-            .padding(0)
-            .width(Length::Fixed(THUMB_SIZE as f32))
-            .height(Length::Fixed(THUMB_SIZE as f32));
-
-        let btn = button(tile)
-            .width(Length::Fixed(THUMB_SIZE as f32))
-            .height(Length::Fixed((THUMB_SIZE / 2) as f32))
-            .style(|_theme, _status| iced::widget::button::Style {
-                text_color: Color::WHITE,
-                border: iced::Border::default(),
-                shadow: iced::Shadow::default(),
-                ..Default::default()
-            })
-            .on_press(Message::SelectWallpaper(path.clone()));
+        let base = if self.config.is_favourite(path) {
+            let badge = container(text("★").size(18).color(Color::from_rgb8(0xff, 0xcc, 0x33)))
+                .width(Length::Fixed(w))
+                .padding(4)
+                .align_right(Length::Fixed(w));
+            stack![base, badge].into()
+        } else {
+            base
+        };
 
         let p = path.clone();
 
-        mouse_area(btn)
+        mouse_area(self.tile_button(base, Message::SelectWallpaper(path.clone())))
             .on_right_press(Message::OpenPreview(p))
             .into()
     }
 
-    fn random_widget(&self) -> Element<'_, Message> {
-        let handle = svg::Handle::from_memory(DICE_SVG.as_bytes());
-        let icon = Svg::new(handle)
-            .width(Length::Fixed(THUMB_SIZE as f32))
-            .height(Length::Fixed((THUMB_SIZE as f32) * 0.65));
+    fn remote_thumbnail_widget<'a>(&'a self, index: usize, wp: &'a Wallpaper, w: &'a WallhavenState) -> Element<'a, Message> {
+        let (tw, th) = self.tile_size();
 
-        let base: Element<Message> = container(column![icon, text("Random")].spacing(6))
-            .width(Length::Fixed(THUMB_SIZE as f32))
-            .height(Length::Fixed(THUMB_SIZE as f32))
-            .into();
+        let base: Element<Message> = if let Some(handle) = w.thumbs.get(&wp.id) {
+            Image::new(handle.clone())
+                .content_fit(ContentFit::Cover)
+                .width(Length::Fixed(tw))
+                .height(Length::Fixed(th))
+                .into()
+        } else {
+            container(text(" "))
+                .width(Length::Fixed(tw))
+                .height(Length::Fixed(th))
+                .into()
+        };
 
-        let tile = container(base)
-            .padding(0)
-            .width(Length::Fixed(THUMB_SIZE as f32))
-            .height(Length::Fixed(THUMB_SIZE as f32));
+        let tile = mouse_area(self.tile_button(base, Message::WallhavenChoose(index)))
+            .on_right_press(Message::WallhavenPreview(index));
 
-        let btn = button(tile)
-            .width(Length::Fixed(THUMB_SIZE as f32))
-            .height(Length::Fixed((THUMB_SIZE / 2) as f32))
-            .style(|_theme, _status| iced::widget::button::Style {
-                text_color: Color::WHITE,
-                border: iced::Border::default(),
-                shadow: iced::Shadow::default(),
-                ..Default::default()
-            })
-            .on_press(Message::SetRandomWallpaper);
+        tooltip(tile, text(wallhaven_ui::describe(wp)), tooltip::Position::Bottom).into()
+    }
 
-        mouse_area(btn).into()
+    fn random_widget(&self) -> Element<'_, Message> {
+        self.action_tile(DICE_SVG, "Random", Message::SetRandomWallpaper)
     }
 
     fn wallhaven_widget(&self) -> Element<'_, Message> {
-        let handle = svg::Handle::from_memory(WALLHAVEN_SVG.as_bytes());
+        self.action_tile(WALLHAVEN_SVG, "Wallhaven", Message::OpenWallhaven)
+    }
+
+    fn duplicates_widget(&self) -> Element<'_, Message> {
+        self.action_tile(DUPLICATES_SVG, "Duplicates", Message::OpenDedupe)
+    }
+
+    fn action_tile(&self, icon_svg: &'static str, label: &'static str, on_press: Message) -> Element<'_, Message> {
+        let (w, h) = self.tile_size();
+        let handle = svg::Handle::from_memory(icon_svg.as_bytes());
         let icon = Svg::new(handle)
-            .width(Length::Fixed(THUMB_SIZE as f32))
-            .height(Length::Fixed((THUMB_SIZE as f32) * 0.65));
+            .width(Length::Fixed(w))
+            .height(Length::Fixed(h * 0.65));
 
-        let base: Element<Message> = container(column![icon, text("Wallhaven"),].spacing(6))
-            .width(Length::Fixed(THUMB_SIZE as f32))
-            .height(Length::Fixed(THUMB_SIZE as f32))
+        let base: Element<Message> = container(column![icon, text(label)].spacing(6))
+            .width(Length::Fixed(w))
+            .height(Length::Fixed(h))
             .into();
 
-        let tile = container(base)
+        mouse_area(self.tile_button(base, on_press)).into()
+    }
+
+    fn tile_button<'a>(&self, content: Element<'a, Message>, on_press: Message) -> Element<'a, Message> {
+        let (w, h) = self.tile_size();
+
+        let tile = container(content)
             .padding(0)
-            .width(Length::Fixed(THUMB_SIZE as f32))
-            .height(Length::Fixed(THUMB_SIZE as f32));
+            .width(Length::Fixed(w))
+            .height(Length::Fixed(h));
 
-        let btn = button(tile)
-            .width(Length::Fixed(THUMB_SIZE as f32))
-            .height(Length::Fixed((THUMB_SIZE / 2) as f32))
+        button(tile)
+            .padding(0)
+            .width(Length::Fixed(w))
+            .height(Length::Fixed(h))
             .style(|_theme, _status| iced::widget::button::Style {
                 text_color: Color::WHITE,
                 border: iced::Border::default(),
                 shadow: iced::Shadow::default(),
                 ..Default::default()
             })
-            .on_press(Message::SetWallhavenWallpaper);
-
-        mouse_area(btn).into()
+            .on_press(on_press)
+            .into()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,312 @@
+use iced::widget::image::Handle as IcedImageHandle;
+use iced::widget::{Image, button, column, container, mouse_area, row, scrollable, text};
+use iced::{Element, Length};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::app::Message;
+use crate::commands::dedupe::{
+    DedupeAction, FileInfo, dedupe_all_except, file_info, find_duplicate_groups, format_size, format_time,
+    newest_index,
+};
+
+#[derive(Debug, Clone)]
+pub struct DuplicateEntry {
+    pub path: PathBuf,
+    pub info: FileInfo,
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateGroupView {
+    pub size: u64,
+    pub entries: Vec<DuplicateEntry>,
+}
+
+#[derive(Debug, Default)]
+pub struct DedupeState {
+    pub scanning: bool,
+    pub groups: Vec<DuplicateGroupView>,
+    pub processed: usize,
+    pub deleted: usize,
+    pub reclaimed: u64,
+    /// `(group, keep_index)` waiting for the user to confirm deleting the rest of the group.
+    pub pending: Option<(usize, usize)>,
+}
+
+pub async fn load_duplicate_groups(folders: Vec<PathBuf>) -> Vec<DuplicateGroupView> {
+    let groups = find_duplicate_groups(folders.clone()).await;
+
+    tokio::task::spawn_blocking(move || {
+        groups
+            .into_iter()
+            .map(|g| DuplicateGroupView {
+                size: g.size,
+                entries: g
+                    .files
+                    .into_iter()
+                    .map(|path| DuplicateEntry {
+                        info: file_info(&path, &folders),
+                        path,
+                    })
+                    .collect(),
+            })
+            .collect()
+    })
+    .await
+    .unwrap_or_default()
+}
+
+impl DedupeState {
+    pub fn paths(&self) -> impl Iterator<Item = &PathBuf> {
+        self.groups.iter().flat_map(|g| g.entries.iter().map(|e| &e.path))
+    }
+
+    /// Asks for confirmation before keeping one file of the group and deleting the rest.
+    pub fn ask_keep(&mut self, group: usize, keep_index: usize) {
+        if self.groups.get(group).is_some_and(|g| keep_index < g.entries.len()) {
+            self.pending = Some((group, keep_index));
+        }
+    }
+
+    pub fn ask_keep_newest(&mut self, group: usize) {
+        let Some(g) = self.groups.get(group) else {
+            return;
+        };
+
+        let files: Vec<PathBuf> = g.entries.iter().map(|e| e.path.clone()).collect();
+        if let Some(keep_index) = newest_index(&files) {
+            self.pending = Some((group, keep_index));
+        }
+    }
+
+    /// Carries out the confirmed [`ask_keep`](Self::ask_keep), returning the paths removed.
+    pub fn confirm(&mut self) -> Vec<PathBuf> {
+        match self.pending.take() {
+            Some((group, keep_index)) => self.keep(group, keep_index),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    /// Keeps one file of the group and deletes the rest, returning the paths that were removed.
+    fn keep(&mut self, group: usize, keep_index: usize) -> Vec<PathBuf> {
+        let Some(g) = self.groups.get(group) else {
+            return Vec::new();
+        };
+        if keep_index >= g.entries.len() {
+            return Vec::new();
+        }
+
+        let g = self.groups.remove(group);
+        let files: Vec<PathBuf> = g.entries.into_iter().map(|e| e.path).collect();
+        let (deleted, bytes) = dedupe_all_except(&files, keep_index, g.size, DedupeAction::Delete);
+
+        self.processed += 1;
+        self.deleted += deleted;
+        self.reclaimed += bytes;
+
+        files
+            .into_iter()
+            .enumerate()
+            .filter(|(i, p)| *i != keep_index && !p.exists())
+            .map(|(_, p)| p)
+            .collect()
+    }
+
+    pub fn skip(&mut self, group: usize) {
+        if group < self.groups.len() {
+            // Group indices shift; a pending confirmation could point at the wrong group.
+            self.pending = None;
+            self.groups.remove(group);
+            self.processed += 1;
+        }
+    }
+
+    /// Follows a file that was moved elsewhere (e.g. from the preview window).
+    pub fn rename(&mut self, from: &PathBuf, to: &Path) {
+        for e in self.groups.iter_mut().flat_map(|g| g.entries.iter_mut()) {
+            if &e.path == from {
+                e.path = to.to_path_buf();
+            }
+        }
+    }
+
+    /// Drops a file that was deleted elsewhere (e.g. from the preview window).
+    pub fn forget(&mut self, path: &PathBuf) {
+        for g in self.groups.iter_mut() {
+            let had = g.entries.len();
+            g.entries.retain(|e| &e.path != path);
+            if g.entries.len() < had {
+                self.deleted += 1;
+                self.reclaimed += g.size;
+            }
+        }
+
+        let was = self.groups.len();
+        self.groups.retain(|g| g.entries.len() >= 2);
+        if self.groups.len() != was {
+            self.pending = None;
+        }
+        self.processed += was - self.groups.len();
+    }
+}
+
+pub(super) fn view<'a>(
+    state: &'a DedupeState,
+    thumbs: &'a HashMap<PathBuf, IcedImageHandle>,
+    thumb_size: u32,
+) -> Element<'a, Message> {
+    let summary = if state.scanning {
+        String::from("Scanning for duplicates…")
+    } else {
+        format!(
+            "{} group(s) left; processed {}, deleted {} file(s), reclaimed {}",
+            state.groups.len(),
+            state.processed,
+            state.deleted,
+            format_size(state.reclaimed)
+        )
+    };
+
+    let header = row![
+        button(text("Back")).on_press(Message::CloseDedupe),
+        text(summary).size(16),
+    ]
+    .spacing(16)
+    .align_y(iced::Alignment::Center);
+
+    let body: Element<Message> = if !state.scanning && state.groups.is_empty() {
+        container(text("No exact duplicates found."))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x(Length::Fill)
+            .center_y(Length::Fill)
+            .into()
+    } else {
+        let groups: Vec<Element<Message>> = state
+            .groups
+            .iter()
+            .enumerate()
+            .map(|(gi, g)| {
+                let pending = state.pending.filter(|(pg, _)| *pg == gi).map(|(_, keep)| keep);
+                group_widget(gi, g, pending, thumbs, thumb_size)
+            })
+            .collect();
+
+        scrollable(column(groups).spacing(24).padding([0, 10]))
+            .height(Length::Fill)
+            .into()
+    };
+
+    container(column![header, body].spacing(16))
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding(10)
+        .into()
+}
+
+fn group_widget<'a>(
+    gi: usize,
+    group: &'a DuplicateGroupView,
+    pending: Option<usize>,
+    thumbs: &'a HashMap<PathBuf, IcedImageHandle>,
+    thumb_size: u32,
+) -> Element<'a, Message> {
+    let title = text(format!(
+        "Group {} ({} file(s), {} each)",
+        gi + 1,
+        group.entries.len(),
+        format_size(group.size)
+    ))
+    .size(16);
+
+    let actions = match pending {
+        Some(keep) => {
+            let name = group.entries[keep]
+                .path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            row![
+                title,
+                text(format!("Delete {} other file(s) and keep '{name}'?", group.entries.len() - 1)),
+                button(text("Delete")).on_press(Message::ConfirmKeepDuplicate),
+                button(text("Cancel")).on_press(Message::CancelKeepDuplicate),
+            ]
+        }
+        None => row![
+            title,
+            button(text("Keep newest")).on_press(Message::KeepNewestDuplicate(gi)),
+            button(text("Skip")).on_press(Message::SkipDuplicates(gi)),
+        ],
+    }
+    .spacing(12)
+    .align_y(iced::Alignment::Center);
+
+    let cards: Vec<Element<Message>> = group
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, e)| entry_widget(gi, i, group.size, e, thumbs, thumb_size))
+        .collect();
+
+    column![actions, row(cards).spacing(12)].spacing(8).into()
+}
+
+fn entry_widget<'a>(
+    gi: usize,
+    index: usize,
+    size: u64,
+    entry: &'a DuplicateEntry,
+    thumbs: &'a HashMap<PathBuf, IcedImageHandle>,
+    thumb_size: u32,
+) -> Element<'a, Message> {
+    let side = Length::Fixed(thumb_size as f32);
+    let thumb: Element<Message> = if let Some(handle) = thumbs.get(&entry.path) {
+        Image::new(handle.clone())
+            .width(side)
+            .height(side)
+            .into()
+    } else {
+        container(text(" "))
+            .width(side)
+            .height(side)
+            .into()
+    };
+
+    let name = entry
+        .path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let info = column![
+        text(name).size(14),
+        text(entry.path.display().to_string()).size(11),
+        text(format!(
+            "{} {}, {}",
+            entry.info.resolution(),
+            entry.info.format_name(),
+            format_size(size)
+        ))
+        .size(12),
+        text(format!("created {}", format_time(entry.info.created))).size(12),
+        text(format!("modified {}", format_time(entry.info.modified))).size(12),
+        text(format!("in {}", entry.info.folder_name())).size(12),
+        button(text("Keep this")).on_press(Message::KeepDuplicate(gi, index)),
+    ]
+    .spacing(4)
+    .width(side);
+
+    let p = entry.path.clone();
+    column![
+        mouse_area(thumb).on_right_press(Message::OpenPreview(p)),
+        info,
+    ]
+    .spacing(6)
+    .into()
+}
@@ -24,4 +24,19 @@ pub const WALLHAVEN_SVG: &str = r#"<?xml version='1.0' encoding='UTF-8'?>
         stroke-width='8'
         stroke-linecap='round'
         stroke-linejoin='round'/>
-</svg>"#;
\ No newline at end of file
+</svg>"#;
+
+pub const DUPLICATES_SVG: &str = r#"<?xml version='1.0' encoding='UTF-8'?>
+<svg width='100' height='100' viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+  <rect x='5' y='5' width='90' height='90' rx='10' fill='#333333'/>
+
+  <rect x='20' y='20' width='40' height='40' rx='4'
+        fill='none'
+        stroke='#FFFFFF'
+        stroke-width='7'/>
+
+  <rect x='40' y='40' width='40' height='40' rx='4'
+        fill='#333333'
+        stroke='#FFFFFF'
+        stroke-width='7'/>
+</svg>"#;
@@ -1,6 +1,9 @@
 pub mod preview;
+pub mod picture;
 pub mod app;
 pub mod icons;
+pub mod dedupe;
+pub mod wallhaven;
 
 pub use app::run;
 
@@ -0,0 +1,310 @@
+use iced::advanced::image::{FilterMethod, Image, Renderer as ImageRenderer};
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::renderer;
+use iced::advanced::widget::{Tree, Widget};
+use iced::advanced::mouse;
+use iced::widget::image::Handle as IcedImageHandle;
+use iced::{Color, Element, Length, Point, Rectangle, Size, Vector};
+
+use image::RgbaImage;
+use image::imageops::{self, FilterType};
+use std::path::Path;
+
+use crate::config::FillMode;
+
+/// Largest texture edge uploaded at once; bigger images are split into tiles of this size.
+const TILE: u32 = 2048;
+
+#[derive(Debug, Clone)]
+struct Tile {
+    handle: IcedImageHandle,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// One resolution of the picture; `scale` is level pixels per source pixel.
+#[derive(Debug, Clone)]
+struct MipLevel {
+    scale: f32,
+    tiles: Vec<Tile>,
+}
+
+/// A full-resolution image split into GPU-friendly tiles, with halved mip levels down to a
+/// single tile so zoomed-out views don't sample the full-size textures.
+#[derive(Debug, Clone)]
+pub struct Picture {
+    pub width: u32,
+    pub height: u32,
+    /// Most common colours, computed from the smallest level.
+    pub dominant: Vec<[u8; 3]>,
+    levels: Vec<MipLevel>,
+}
+
+impl Picture {
+    pub fn load(path: &Path) -> Option<Self> {
+        let img = image::open(path).ok()?.to_rgba8();
+        let (width, height) = img.dimensions();
+
+        let mut levels = Vec::new();
+        let mut current = img;
+        let mut scale = 1.0f32;
+        loop {
+            levels.push(MipLevel {
+                scale,
+                tiles: split_tiles(&current),
+            });
+
+            let (w, h) = current.dimensions();
+            if w <= TILE && h <= TILE {
+                break;
+            }
+            current = imageops::resize(&current, (w / 2).max(1), (h / 2).max(1), FilterType::Triangle);
+            scale /= 2.0;
+        }
+
+        let dominant = crate::image::dominant_colors(&current, 5);
+
+        Some(Self {
+            width,
+            height,
+            dominant,
+            levels,
+        })
+    }
+
+    /// The coarsest level that still has at least one texel per screen pixel at `zoom`.
+    fn level_for(&self, zoom: f32) -> &MipLevel {
+        self.levels
+            .iter()
+            .rev()
+            .find(|l| l.scale >= zoom)
+            .unwrap_or(&self.levels[0])
+    }
+}
+
+fn split_tiles(img: &RgbaImage) -> Vec<Tile> {
+    let (w, h) = img.dimensions();
+    let mut tiles = Vec::new();
+
+    for y in (0..h).step_by(TILE as usize) {
+        for x in (0..w).step_by(TILE as usize) {
+            let tw = TILE.min(w - x);
+            let th = TILE.min(h - y);
+            let sub = imageops::crop_imm(img, x, y, tw, th).to_image();
+            tiles.push(Tile {
+                handle: IcedImageHandle::from_rgba(tw, th, sub.into_raw()),
+                x,
+                y,
+                width: tw,
+                height: th,
+            });
+        }
+    }
+
+    tiles
+}
+
+/// The largest rectangle with `content`'s aspect ratio, centred in `bounds`.
+pub fn fit_rect(bounds: Rectangle, content: Size) -> Rectangle {
+    let scale = (bounds.width / content.width).min(bounds.height / content.height);
+    let size = Size::new(content.width * scale, content.height * scale);
+    Rectangle {
+        x: bounds.x + (bounds.width - size.width) / 2.0,
+        y: bounds.y + (bounds.height - size.height) / 2.0,
+        width: size.width,
+        height: size.height,
+    }
+}
+
+/// Draws `picture` with its top-left corner at `origin`, `kx`/`ky` screen pixels per image
+/// pixel horizontally/vertically, skipping tiles outside `clip`.
+fn draw_picture<Renderer>(renderer: &mut Renderer, picture: &Picture, origin: Point, kx: f32, ky: f32, clip: Rectangle)
+where
+    Renderer: ImageRenderer<Handle = IcedImageHandle>,
+{
+    let zoom = kx.min(ky);
+    let level = picture.level_for(zoom);
+    // Screen pixels per level pixel.
+    let (lx, ly) = (kx / level.scale, ky / level.scale);
+    let filter = if zoom >= 2.0 { FilterMethod::Nearest } else { FilterMethod::Linear };
+
+    for tile in level.tiles.iter() {
+        let rect = Rectangle {
+            x: origin.x + tile.x as f32 * lx,
+            y: origin.y + tile.y as f32 * ly,
+            width: tile.width as f32 * lx,
+            height: tile.height as f32 * ly,
+        };
+        if rect.intersects(&clip) {
+            renderer.draw_image(Image::new(tile.handle.clone()).filter_method(filter), rect);
+        }
+    }
+}
+
+/// Draws a [`Picture`] at `zoom` screen pixels per image pixel, with the image's top-left
+/// corner at `offset` from the widget's top-left.
+pub struct PictureView<'a> {
+    picture: &'a Picture,
+    zoom: f32,
+    offset: Vector,
+}
+
+pub fn picture_view(picture: &Picture, zoom: f32, offset: Vector) -> PictureView<'_> {
+    PictureView { picture, zoom, offset }
+}
+
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> for PictureView<'_>
+where
+    Renderer: ImageRenderer<Handle = IcedImageHandle>,
+{
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fill, Length::Fill)
+    }
+
+    fn layout(&self, _tree: &mut Tree, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        layout::Node::new(limits.max())
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let origin = bounds.position() + self.offset;
+
+        renderer.with_layer(bounds, |renderer| {
+            draw_picture(renderer, self.picture, origin, self.zoom, self.zoom, bounds);
+        });
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<PictureView<'a>> for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: ImageRenderer<Handle = IcedImageHandle> + 'a,
+{
+    fn from(view: PictureView<'a>) -> Self {
+        Element::new(view)
+    }
+}
+
+/// Shows how a wallpaper backend would place a [`Picture`] on a `screen`-sized output with the
+/// given fill mode, scaled down to the widget and optionally covered by a bar/panel mockup.
+pub struct DesktopView<'a> {
+    picture: &'a Picture,
+    screen: Size,
+    mode: FillMode,
+    background: Color,
+    mockup: Option<&'a IcedImageHandle>,
+}
+
+pub fn desktop_view<'a>(
+    picture: &'a Picture,
+    screen: Size,
+    mode: FillMode,
+    background: Color,
+    mockup: Option<&'a IcedImageHandle>,
+) -> DesktopView<'a> {
+    DesktopView {
+        picture,
+        screen,
+        mode,
+        background,
+        mockup,
+    }
+}
+
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> for DesktopView<'_>
+where
+    Renderer: ImageRenderer<Handle = IcedImageHandle>,
+{
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fill, Length::Fill)
+    }
+
+    fn layout(&self, _tree: &mut Tree, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        layout::Node::new(limits.max())
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let screen = fit_rect(layout.bounds(), self.screen);
+        // Widget pixels per output pixel.
+        let s = screen.width / self.screen.width;
+        let (iw, ih) = (self.picture.width as f32, self.picture.height as f32);
+        let (sw, sh) = (self.screen.width, self.screen.height);
+
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds: screen,
+                ..Default::default()
+            },
+            self.background,
+        );
+
+        renderer.with_layer(screen, |renderer| {
+            let centred = |k: f32| {
+                Point::new(
+                    screen.x + (screen.width - iw * k) / 2.0,
+                    screen.y + (screen.height - ih * k) / 2.0,
+                )
+            };
+
+            match self.mode {
+                FillMode::Fill => {
+                    let k = (sw / iw).max(sh / ih) * s;
+                    draw_picture(renderer, self.picture, centred(k), k, k, screen);
+                }
+                FillMode::Fit => {
+                    let k = (sw / iw).min(sh / ih) * s;
+                    draw_picture(renderer, self.picture, centred(k), k, k, screen);
+                }
+                FillMode::Center => {
+                    draw_picture(renderer, self.picture, centred(s), s, s, screen);
+                }
+                FillMode::Stretch => {
+                    let (kx, ky) = (sw / iw * s, sh / ih * s);
+                    draw_picture(renderer, self.picture, screen.position(), kx, ky, screen);
+                }
+                FillMode::Tile => {
+                    let (cols, rows) = ((sw / iw).ceil() as u32, (sh / ih).ceil() as u32);
+                    for row in 0..rows {
+                        for col in 0..cols {
+                            let origin = screen.position()
+                                + Vector::new(col as f32 * iw * s, row as f32 * ih * s);
+                            draw_picture(renderer, self.picture, origin, s, s, screen);
+                        }
+                    }
+                }
+            }
+
+            if let Some(mockup) = self.mockup {
+                renderer.draw_image(Image::new(mockup.clone()), screen);
+            }
+        });
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<DesktopView<'a>> for Element<'a, Message, Theme, Renderer>
+where
+    Renderer: ImageRenderer<Handle = IcedImageHandle> + 'a,
+{
+    fn from(view: DesktopView<'a>) -> Self {
+        Element::new(view)
+    }
+}
@@ -1,32 +1,81 @@
 use iced::event::Event;
-use iced::{keyboard, window};
-use iced::widget::{container, text, Image};
+use iced::{keyboard, mouse, window};
 use iced::widget::image::Handle as IcedImageHandle;
-use iced::{application, Element, Length, Size, Task, Theme, ContentFit};
+use iced::widget::{column, container, row, stack, text};
+use iced::{application, Border, Color, Element, Length, Point, Rectangle, Size, Task, Theme, Vector};
 
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use image::GenericImageView;
+use super::picture::{Picture, desktop_view, fit_rect, picture_view};
+use crate::config::{Config, FillMode};
+use crate::commands::dedupe::{FileInfo, file_info, format_size, format_time};
+use crate::commands::PreviewEvent;
+use crate::wallhaven::Metadata;
 
 #[derive(Debug)]
 struct PreviewApp {
     title: String,
-    handle: Option<IcedImageHandle>,
-    path: PathBuf,
+    /// Images browsable with Left/Right, and the one being shown.
+    list: Vec<PathBuf>,
+    index: usize,
+    /// Decoded pictures and header info for the shown image and its neighbours.
+    loaded: HashMap<PathBuf, Loaded>,
+    pending: HashSet<PathBuf>,
+    config: Config,
+    show_info: bool,
+    /// Desktop simulation: how the image would look on the real output with this fill mode.
+    simulate: Option<FillMode>,
+    screen: Size,
+    mockup: Option<IcedImageHandle>,
+    viewport: Size,
+    cursor: Point,
+    zoom: f32,
+    offset: Vector,
+    /// Keep the image fitted to the window (re-fitted on resize) until the user zooms or pans.
+    fit: bool,
+    /// Where the left button went down, and the last cursor position while dragging.
+    pressed_at: Option<Point>,
+    dragging: Option<Point>,
+}
+
+#[derive(Debug, Clone)]
+struct Loaded {
+    /// `None` if decoding failed.
+    picture: Option<Picture>,
+    info: FileInfo,
+    /// Attribution and tags if the file came from Wallhaven.
+    wallhaven: Option<Box<Metadata>>,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     EventOccurred(Event),
-    LoadedPreview(Option<IcedImageHandle>),
+    LoadedPreview(PathBuf, Loaded),
+    WallpaperSet,
 }
 
-const MAX_PREVIEW_W: u32 = 1920;
+const MIN_ZOOM: f32 = 0.02;
+const MAX_ZOOM: f32 = 32.0;
+/// Zoom factor per wheel notch.
+const ZOOM_STEP: f32 = 1.25;
+/// Cursor travel (in logical pixels) below which a press/release counts as a click, not a drag.
+const CLICK_SLOP: f32 = 4.0;
 
-pub fn run(path: PathBuf) -> iced::Result {
+pub fn run(path: PathBuf, mut list: Vec<PathBuf>) -> iced::Result {
     let title = String::from("Wallpicker - Preview");
 
-    application(|s: &PreviewApp| s.title.clone(), update, view)
+    let index = match list.iter().position(|p| p == &path) {
+        Some(i) => i,
+        None => {
+            list.insert(0, path);
+            0
+        }
+    };
+    let cfg = crate::config::load_config();
+    let screen = crate::image::screen_resolution();
+
+    application(PreviewApp::title, update, view)
         .theme(|_| Theme::Dark)
         .antialiasing(true)
         .window(window::Settings {
@@ -36,73 +85,413 @@ pub fn run(path: PathBuf) -> iced::Result {
             platform_specific: super::platform_specific_settings("wallpicker-preview"),
             ..Default::default()
         })
-        .subscription(|_state| iced::event::listen().map(Message::EventOccurred))
+        .subscription(|_state| iced::event::listen_with(|event, _status, _id| Some(event)).map(Message::EventOccurred))
         .run_with(move || {
-            let p = path.clone();
-            (
-                PreviewApp {
-                    title: title.clone(),
-                    handle: None,
-                    path: path.clone(),
-                },
-                Task::perform(load_preview_handle(p), Message::LoadedPreview),
-            )
+            let mut state = PreviewApp {
+                title: title.clone(),
+                list: list.clone(),
+                index,
+                loaded: HashMap::new(),
+                pending: HashSet::new(),
+                config: cfg.clone(),
+                show_info: false,
+                simulate: None,
+                screen: Size::new(screen.0 as f32, screen.1 as f32),
+                mockup: cfg.desktop_mockup.clone().map(IcedImageHandle::from_path),
+                viewport: Size::new(1920.0, 1080.0),
+                cursor: Point::ORIGIN,
+                zoom: 1.0,
+                offset: Vector::ZERO,
+                fit: true,
+                pressed_at: None,
+                dragging: None,
+            };
+            let task = state.load_neighbourhood();
+            (state, task)
         })
 }
 
-async fn load_preview_handle(path: PathBuf) -> Option<IcedImageHandle> {
-    tokio::task::spawn_blocking(move || {
-        let img = image::open(&path).ok()?;
+async fn load_picture(path: PathBuf, folders: Vec<PathBuf>) -> Loaded {
+    let for_info = path.clone();
+    let picture = tokio::task::spawn_blocking(move || Picture::load(&path))
+        .await
+        .unwrap_or(None);
+
+    Loaded {
+        picture,
+        info: file_info(&for_info, &folders),
+        wallhaven: crate::wallhaven::metadata::for_path(&for_info).map(Box::new),
+    }
+}
 
-        let (w, h) = img.dimensions();
-        let scaled = if w > MAX_PREVIEW_W {
-            let scale = MAX_PREVIEW_W as f32 / w as f32;
-            let target_h = ((h as f32) * scale).round().max(1.0) as u32;
+impl PreviewApp {
+    fn title(&self) -> String {
+        let name = self
+            .path()
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let position = if self.list.len() > 1 {
+            format!(" [{}/{}]", self.index + 1, self.list.len())
+        } else {
+            String::new()
+        };
 
-            img.resize(
-                MAX_PREVIEW_W,
-                target_h,
-                image::imageops::FilterType::CatmullRom,
+        if let Some(mode) = self.simulate {
+            format!(
+                "{} - {}{} (desktop {}x{}, {})",
+                self.title,
+                name,
+                position,
+                self.screen.width,
+                self.screen.height,
+                mode.label()
             )
+        } else if self.picture().is_some() {
+            format!("{} - {}{} ({:.0}%)", self.title, name, position, self.zoom * 100.0)
         } else {
-            img
+            format!("{} - {}{}", self.title, name, position)
+        }
+    }
+
+    fn path(&self) -> &PathBuf {
+        &self.list[self.index]
+    }
+
+    fn picture(&self) -> Option<&Picture> {
+        self.loaded.get(self.path()).and_then(|l| l.picture.as_ref())
+    }
+
+    fn image_size(&self) -> Option<Size> {
+        self.picture()
+            .map(|p| Size::new(p.width as f32, p.height as f32))
+    }
+
+    /// The shown image and its neighbours, in load priority order.
+    fn neighbourhood(&self) -> Vec<PathBuf> {
+        let n = self.list.len();
+        let mut paths = vec![self.list[self.index].clone()];
+        for i in [(self.index + 1) % n, (self.index + n - 1) % n] {
+            if !paths.contains(&self.list[i]) {
+                paths.push(self.list[i].clone());
+            }
+        }
+        paths
+    }
+
+    /// Starts decoding the shown image and preloading its neighbours, and drops anything
+    /// further away so only a few full-resolution pictures are held at once.
+    fn load_neighbourhood(&mut self) -> Task<Message> {
+        let wanted = self.neighbourhood();
+        self.loaded.retain(|p, _| wanted.contains(p));
+
+        let missing: Vec<PathBuf> = wanted
+            .into_iter()
+            .filter(|p| !self.loaded.contains_key(p) && !self.pending.contains(p))
+            .collect();
+
+        let tasks: Vec<Task<Message>> = missing
+            .into_iter()
+            .map(|p| {
+                self.pending.insert(p.clone());
+                let for_msg = p.clone();
+                Task::perform(load_picture(p, self.config.folders.clone()), move |l| {
+                    Message::LoadedPreview(for_msg.clone(), l)
+                })
+            })
+            .collect();
+
+        Task::batch(tasks)
+    }
+
+    fn step(&mut self, delta: isize) -> Task<Message> {
+        let n = self.list.len() as isize;
+        if n < 2 && delta != 0 {
+            return Task::none();
+        }
+
+        self.index = (self.index as isize + delta).rem_euclid(n) as usize;
+        self.pressed_at = None;
+        self.dragging = None;
+        self.fit = true;
+        self.apply_fit();
+        if self.simulate.is_some() {
+            self.simulate = Some(self.config.placement_for(self.path()).mode);
+        }
+
+        self.load_neighbourhood()
+    }
+
+    /// Saves `mode` as the shown image's fill mode. The config is re-read first so settings
+    /// the picker saved while the preview was open aren't lost.
+    fn remember_fill_mode(&mut self, mode: FillMode) {
+        let path = self.path().clone();
+        if self.config.placement_for(&path).mode == mode {
+            return;
+        }
+
+        let mut cfg = crate::config::load_config();
+        cfg.set_fill_mode_for(&path, mode);
+        let _ = crate::config::save_config(&cfg);
+        self.config = cfg;
+    }
+
+    /// Opens the Wallhaven page of the current image in the browser, if it came from there.
+    fn open_wallhaven_page(&self) {
+        let url = match self.loaded.get(self.path()).and_then(|l| l.wallhaven.as_ref()) {
+            Some(meta) => meta.page_url(&self.config),
+            None => match crate::wallhaven::wallhaven_id(self.path()) {
+                Some(id) => crate::wallhaven::page_url(&self.config, &id),
+                None => return,
+            },
         };
+        let _ = crate::platform::open_url(&url);
+    }
+
+    /// Keeps the current Wallhaven image (or, with `uploader`, everything by its uploader) out
+    /// of random picks from now on.
+    fn blacklist(&mut self, uploader: bool) {
+        let loaded = self.loaded.get(self.path()).and_then(|l| l.wallhaven.as_deref());
+        let mut cfg = crate::config::load_config();
+        if uploader {
+            let Some(name) = loaded.and_then(|m| m.uploader_name()) else {
+                return;
+            };
+            cfg.wallhaven_blacklist.add_uploader(name);
+        } else {
+            let Some(id) = loaded
+                .map(|m| m.id.clone())
+                .or_else(|| crate::wallhaven::wallhaven_id(self.path()))
+            else {
+                return;
+            };
+            cfg.wallhaven_blacklist.add_id(&id);
+        }
+        let _ = crate::config::save_config(&cfg);
+        self.config = cfg;
+        // The info panel says what's blacklisted.
+        self.show_info = true;
+    }
+
+    /// Marks the shown image as a favourite, or unmarks it.
+    fn toggle_favourite(&mut self) {
+        let path = self.path().clone();
+        let mut cfg = crate::config::load_config();
+        let favourite = cfg.toggle_favourite(&path);
+        let _ = crate::config::save_config(&cfg);
+        self.config = cfg;
+        PreviewEvent::Favourited { path, favourite }.report();
+    }
+
+    /// Moves the shown image into configured folder `folder`, keeping its name. Nothing
+    /// happens if it's already there or a file of that name exists.
+    fn move_to_folder(&mut self, folder: usize) {
+        let Some(dir) = self.config.folders.get(folder) else {
+            return;
+        };
+        let from = self.path().clone();
+        let Some(name) = from.file_name() else {
+            return;
+        };
+        let to = dir.join(name);
+        if from.parent() == Some(dir.as_path()) || to.exists() {
+            return;
+        }
+
+        // A plain rename fails across filesystems. The copy only counts once the original is
+        // gone; otherwise it is removed again rather than leaving a duplicate behind.
+        let moved = std::fs::rename(&from, &to).is_ok() || {
+            let copied = std::fs::copy(&from, &to).is_ok() && std::fs::remove_file(&from).is_ok();
+            if !copied {
+                let _ = std::fs::remove_file(&to);
+            }
+            copied
+        };
+        if !moved {
+            return;
+        }
+        PreviewEvent::Moved {
+            from: from.clone(),
+            to: to.clone(),
+        }
+        .report();
+
+        let mut cfg = crate::config::load_config();
+        cfg.rename_path(&from, &to);
+        let _ = crate::config::save_config(&cfg);
+        self.config = cfg;
+
+        if let Some(mut loaded) = self.loaded.remove(&from) {
+            loaded.info = file_info(&to, &self.config.folders);
+            self.loaded.insert(to.clone(), loaded);
+        }
+        self.list[self.index] = to;
+    }
+
+    /// Deletes the shown image and moves on to the next one, closing once none are left.
+    fn delete_current(&mut self) -> Task<Message> {
+        let path = self.path().clone();
+        if std::fs::remove_file(&path).is_err() {
+            return Task::none();
+        }
+        PreviewEvent::Deleted { path: path.clone() }.report();
+
+        self.loaded.remove(&path);
+        self.list.remove(self.index);
+        if self.list.is_empty() {
+            std::process::exit(0);
+        }
+        if self.index >= self.list.len() {
+            self.index = 0;
+        }
+
+        self.step(0)
+    }
+
+    fn apply_fit(&mut self) {
+        let Some(img) = self.image_size() else { return };
+        self.fit = true;
+        self.zoom = (self.viewport.width / img.width)
+            .min(self.viewport.height / img.height)
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center();
+    }
+
+    fn center(&mut self) {
+        let Some(img) = self.image_size() else { return };
+        self.offset = Vector::new(
+            (self.viewport.width - img.width * self.zoom) / 2.0,
+            (self.viewport.height - img.height * self.zoom) / 2.0,
+        );
+    }
 
-        let rgba = scaled.to_rgba8();
-        let (w2, h2) = rgba.dimensions();
-        Some(IcedImageHandle::from_rgba(w2, h2, rgba.into_raw()))
-    })
-    .await
-    .unwrap_or(None)
+    /// Zooms to `zoom`, keeping the image point under `anchor` where it is on screen.
+    fn zoom_around(&mut self, anchor: Point, zoom: f32) {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        let ratio = zoom / self.zoom;
+        self.offset = Vector::new(
+            anchor.x - (anchor.x - self.offset.x) * ratio,
+            anchor.y - (anchor.y - self.offset.y) * ratio,
+        );
+        self.zoom = zoom;
+        self.fit = false;
+    }
+
+    fn is_over_image(&self, p: Point) -> bool {
+        if self.simulate.is_some() {
+            return fit_rect(Rectangle::with_size(self.viewport), self.screen).contains(p);
+        }
+
+        let Some(img) = self.image_size() else { return false };
+        let x = (p.x - self.offset.x) / self.zoom;
+        let y = (p.y - self.offset.y) / self.zoom;
+        (0.0..img.width).contains(&x) && (0.0..img.height).contains(&y)
+    }
 }
 
 fn update(state: &mut PreviewApp, message: Message) -> Task<Message> {
     match message {
-        Message::LoadedPreview(handle) => {
-            state.handle = handle;
+        Message::LoadedPreview(path, loaded) => {
+            state.pending.remove(&path);
+            if !state.neighbourhood().contains(&path) {
+                return Task::none();
+            }
+
+            let current = &path == state.path();
+            state.loaded.insert(path, loaded);
+            if current {
+                state.apply_fit();
+            }
+        }
+        Message::WallpaperSet => {
+            PreviewEvent::WallpaperSet {
+                path: state.path().clone(),
+            }
+            .report();
+            std::process::exit(0);
         }
         Message::EventOccurred(event) => match event {
-            Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) => {
-                if state.handle.is_some() {
-                    match key {
-                        keyboard::Key::Character(c) if c.eq_ignore_ascii_case("d") => {
-                            let _ = std::fs::remove_file(&state.path);
-                            std::process::exit(10);
-                        }
-                        keyboard::Key::Named(iced::keyboard::key::Named::Delete) => {
-                            let _ = std::fs::remove_file(&state.path);
-                            std::process::exit(10);
-                        }
-                        _ => {
-                            std::process::exit(0);
-                        }
+            Event::Window(window::Event::Opened { size, .. }) | Event::Window(window::Event::Resized(size)) => {
+                state.viewport = size;
+                if state.fit {
+                    state.apply_fit();
+                }
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => match key {
+                keyboard::Key::Named(keyboard::key::Named::Escape) => std::process::exit(0),
+                keyboard::Key::Character(c) if c.eq_ignore_ascii_case("q") => std::process::exit(0),
+                keyboard::Key::Named(keyboard::key::Named::ArrowRight) => return state.step(1),
+                keyboard::Key::Named(keyboard::key::Named::ArrowLeft) => return state.step(-1),
+                keyboard::Key::Character(c) if c.eq_ignore_ascii_case("i") => state.show_info = !state.show_info,
+                keyboard::Key::Character(c) if c.eq_ignore_ascii_case("w") => state.open_wallhaven_page(),
+                keyboard::Key::Character(c) if c.eq_ignore_ascii_case("b") => state.blacklist(false),
+                keyboard::Key::Character(c) if c.eq_ignore_ascii_case("u") => state.blacklist(true),
+                keyboard::Key::Character(c) if c.eq_ignore_ascii_case("l") => state.toggle_favourite(),
+                // Alt+1…9 moves the image into the first…ninth configured folder.
+                keyboard::Key::Character(c) if modifiers.alt() && c.parse::<usize>().is_ok_and(|n| n > 0) => {
+                    state.move_to_folder(c.parse::<usize>().unwrap_or(1) - 1);
+                }
+                _ if state.picture().is_none() => {}
+                keyboard::Key::Named(keyboard::key::Named::Enter) => {
+                    let path = state.path().clone();
+                    // Setting the wallpaper from the simulation keeps the mode being shown.
+                    if let Some(mode) = state.simulate {
+                        state.remember_fill_mode(mode);
                     }
-                } else {
-                    std::process::exit(0);
+                    return Task::perform(
+                        crate::commands::set_wallpaper(path, state.config.clone()),
+                        |_| Message::WallpaperSet,
+                    );
+                }
+                keyboard::Key::Character(c) if c.eq_ignore_ascii_case("d") => return state.delete_current(),
+                keyboard::Key::Named(keyboard::key::Named::Delete) => return state.delete_current(),
+                keyboard::Key::Character(c) if c.as_str() == "1" => {
+                    let center = Point::new(state.viewport.width / 2.0, state.viewport.height / 2.0);
+                    state.zoom_around(center, 1.0);
+                }
+                keyboard::Key::Character(c) if c.eq_ignore_ascii_case("f") => state.apply_fit(),
+                keyboard::Key::Character(c) if c.eq_ignore_ascii_case("s") => {
+                    state.simulate = match state.simulate {
+                        Some(_) => None,
+                        None => Some(state.config.placement_for(state.path()).mode),
+                    };
                 }
+                keyboard::Key::Character(c) if c.eq_ignore_ascii_case("m") => {
+                    state.simulate = state.simulate.map(FillMode::next);
+                }
+                _ => {}
+            },
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                if let Some(last) = state.dragging
+                    && state.simulate.is_none()
+                {
+                    state.offset = state.offset + (position - last);
+                    state.dragging = Some(position);
+                    state.fit = false;
+                }
+                state.cursor = position;
+            }
+            Event::Mouse(mouse::Event::WheelScrolled { delta })
+                if state.picture().is_some() && state.simulate.is_none() =>
+            {
+                let notches = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y / 50.0,
+                };
+                state.zoom_around(state.cursor, state.zoom * ZOOM_STEP.powf(notches));
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                state.pressed_at = Some(state.cursor);
+                state.dragging = Some(state.cursor);
             }
-            Event::Mouse(iced::mouse::Event::ButtonPressed(_)) => {
-                std::process::exit(0);
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                state.dragging = None;
+                if let Some(start) = state.pressed_at.take()
+                    && start.distance(state.cursor) < CLICK_SLOP
+                    && !state.is_over_image(state.cursor)
+                {
+                    std::process::exit(0);
+                }
             }
             _ => {}
         },
@@ -111,14 +500,22 @@ fn update(state: &mut PreviewApp, message: Message) -> Task<Message> {
 }
 
 fn view(state: &'_ PreviewApp) -> Element<'_, Message> {
-    let content: Element<Message> = if let Some(handle) = &state.handle {
-        Image::new(handle.clone())
-            .content_fit(ContentFit::Contain)
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into()
+    let content: Element<Message> = if let Some(picture) = state.picture() {
+        match state.simulate {
+            Some(mode) => {
+                let [r, g, b] = state.config.placement_for(state.path()).background;
+                let background = Color::from_rgb8(r, g, b);
+                desktop_view(picture, state.screen, mode, background, state.mockup.as_ref()).into()
+            }
+            None => picture_view(picture, state.zoom, state.offset).into(),
+        }
     } else {
-        container(text("Loading preview…"))
+        let status = if state.loaded.contains_key(state.path()) {
+            format!("Unable to load {}", state.path().display())
+        } else {
+            String::from("Loading preview…")
+        };
+        container(text(status))
             .width(Length::Fill)
             .height(Length::Fill)
             .center_x(Length::Fill)
@@ -126,9 +523,131 @@ fn view(state: &'_ PreviewApp) -> Element<'_, Message> {
             .into()
     };
 
+    let content: Element<Message> = match state.loaded.get(state.path()) {
+        Some(loaded) if state.show_info => {
+            stack![content, info_overlay(state.path(), loaded, &state.config)].into()
+        }
+        _ => content,
+    };
+
     container(content)
         .width(Length::Fill)
         .height(Length::Fill)
         .padding(0)
         .into()
 }
+
+fn info_overlay<'a>(path: &Path, loaded: &'a Loaded, config: &Config) -> Element<'a, Message> {
+    let info = &loaded.info;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let dir = path
+        .parent()
+        .map(|p| p.display().to_string())
+        .unwrap_or_default();
+
+    let aspect = match info.dimensions {
+        Some((w, h)) => {
+            let (sw, sh) = crate::image::screen_aspect();
+            let ratio = (w as f32 / h.max(1) as f32) / (sw as f32 / sh as f32);
+            let fit = if (ratio - 1.0).abs() < 0.01 {
+                "matches screen"
+            } else if ratio > 1.0 {
+                "wider than screen"
+            } else {
+                "taller than screen"
+            };
+            format!("{} ({}, screen {sw}:{sh})", crate::image::aspect_label(w, h), fit)
+        }
+        None => String::from("unknown"),
+    };
+
+    let mut lines = column![
+        text(name).size(18),
+        text(dir),
+        text(format!(
+            "{} · {} · {}",
+            info.resolution(),
+            info.format_name(),
+            format_size(info.len)
+        )),
+        text(format!("Aspect: {aspect}")),
+        text(format!("Modified: {}", format_time(info.modified))),
+        text(format!("Folder: {}", info.folder_name())),
+    ]
+    .spacing(4);
+
+    if config.is_favourite(path) {
+        lines = lines.push(text("★ Favourite (l to unmark)"));
+    }
+
+    if let Some(meta) = &loaded.wallhaven {
+        lines = lines.push(text(format!("Wallhaven: {} (w to open)", meta.page_url(config))));
+        let mut credit = vec![meta.category.clone(), meta.purity.clone()];
+        if let Some(name) = meta.uploader_name() {
+            credit.insert(0, format!("by {name}"));
+        }
+        credit.retain(|s| !s.is_empty());
+        if !credit.is_empty() {
+            lines = lines.push(text(credit.join(" · ")));
+        }
+        if !meta.source.is_empty() {
+            lines = lines.push(text(format!("Source: {}", meta.source)));
+        }
+        if !meta.tags.is_empty() {
+            lines = lines.push(text(format!("Tags: {}", meta.tag_names().join(", "))));
+        }
+    } else if let Some(id) = crate::wallhaven::wallhaven_id(path) {
+        lines = lines.push(text(format!("Wallhaven: {} (w to open)", crate::wallhaven::page_url(config, &id))));
+    }
+
+    let blacklist = &config.wallhaven_blacklist;
+    let id = loaded
+        .wallhaven
+        .as_ref()
+        .map(|m| m.id.clone())
+        .or_else(|| crate::wallhaven::wallhaven_id(path));
+    if let Some(id) = id {
+        let uploader = loaded.wallhaven.as_ref().and_then(|m| m.uploader_name());
+        let status = if blacklist.has_id(&id) {
+            String::from("Blacklisted")
+        } else if let Some(name) = uploader.filter(|u| blacklist.has_uploader(u)) {
+            format!("Uploader {name} is blacklisted")
+        } else if uploader.is_some() {
+            String::from("b to blacklist, u to blacklist the uploader")
+        } else {
+            String::from("b to blacklist")
+        };
+        lines = lines.push(text(status));
+    }
+
+    if let Some(picture) = &loaded.picture {
+        let swatches = picture.dominant.iter().map(|c| {
+            let color = Color::from_rgb8(c[0], c[1], c[2]);
+            container(text(""))
+                .width(Length::Fixed(28.0))
+                .height(Length::Fixed(28.0))
+                .style(move |_theme| container::Style {
+                    background: Some(color.into()),
+                    border: Border::default().rounded(4),
+                    ..Default::default()
+                })
+                .into()
+        });
+        lines = lines.push(row(swatches).spacing(6));
+    }
+
+    let panel = container(lines)
+        .padding(12)
+        .max_width(560)
+        .style(|_theme| container::Style {
+            background: Some(Color::from_rgba(0.0, 0.0, 0.0, 0.7).into()),
+            text_color: Some(Color::WHITE),
+            border: Border::default().rounded(6),
+            ..Default::default()
+        });
+
+    container(panel).padding(16).into()
+}
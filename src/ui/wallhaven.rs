@@ -0,0 +1,98 @@
+use iced::widget::image::Handle as IcedImageHandle;
+use iced::widget::{button, column, row, text};
+use iced::{Element, Length};
+
+use std::collections::HashMap;
+
+use super::app::Message;
+use crate::commands::dedupe::format_size;
+use crate::wallhaven::{SearchPage, Wallpaper};
+
+/// The Wallhaven browser: one page of search results at a time.
+#[derive(Debug, Default)]
+pub struct WallhavenState {
+    pub loading: bool,
+    pub page: u32,
+    pub last_page: u32,
+    /// Seed of the first page, so later pages continue the same random order.
+    pub seed: Option<String>,
+    pub items: Vec<Wallpaper>,
+    pub thumbs: HashMap<String, IcedImageHandle>,
+    /// What's being fetched at full size, for the status line.
+    pub fetching: Option<String>,
+    pub error: Option<String>,
+}
+
+impl WallhavenState {
+    pub fn show(&mut self, page: SearchPage) {
+        self.loading = false;
+        self.error = None;
+        self.page = page.page;
+        self.last_page = page.last_page;
+        if self.seed.is_none() {
+            self.seed = page.seed;
+        }
+        self.items = page.items;
+        self.thumbs.clear();
+    }
+
+    pub fn find(&self, id: &str) -> Option<usize> {
+        self.items.iter().position(|w| w.id == id)
+    }
+}
+
+/// Thumbnail URL to use for a tile of `thumb_size` pixels.
+pub fn thumb_url(wp: &Wallpaper, thumb_size: u32) -> &str {
+    if thumb_size > 200 || wp.thumbs.small.is_empty() {
+        &wp.thumbs.large
+    } else {
+        &wp.thumbs.small
+    }
+}
+
+/// Hover text for a result tile.
+pub fn describe(wp: &Wallpaper) -> String {
+    format!("{} · {}", wp.resolution, format_size(wp.file_size))
+}
+
+pub(super) fn view<'a>(state: &'a WallhavenState, grid: Element<'a, Message>) -> Element<'a, Message> {
+    let page = if state.last_page > 0 {
+        format!("Page {} of {}", state.page, state.last_page)
+    } else {
+        String::new()
+    };
+
+    let prev = button(text("Previous"))
+        .on_press_maybe((!state.loading && state.page > 1).then(|| Message::WallhavenGoto(state.page - 1)));
+    let next = button(text("Next"))
+        .on_press_maybe((!state.loading && state.page < state.last_page).then(|| Message::WallhavenGoto(state.page + 1)));
+
+    let status = if let Some(e) = &state.error {
+        e.clone()
+    } else if let Some(f) = &state.fetching {
+        format!("Fetching {f}…")
+    } else if state.loading {
+        String::from("Searching…")
+    } else if state.items.is_empty() {
+        String::from("No results.")
+    } else {
+        String::from("Click to download and set, right-click to preview.")
+    };
+
+    let header = row![
+        button(text("Back")).on_press(Message::CloseWallhaven),
+        prev,
+        text(page).size(16),
+        next,
+        button(text("Random")).on_press(Message::SetWallhavenWallpaper),
+        text(status).size(14),
+    ]
+    .spacing(16)
+    .align_y(iced::Alignment::Center);
+
+    column![header, grid]
+        .spacing(16)
+        .padding([0, 10])
+        .height(Length::Fill)
+        .into()
+}
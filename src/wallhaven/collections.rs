@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::downloads::LocalFiles;
+use super::{WallhavenError, WallhavenResponse, Wallpaper, api_timeout, api_url, get};
+use crate::config::{self, Config};
+
+#[derive(Debug, Deserialize)]
+struct CollectionsResponse {
+    data: Vec<Collection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Collection {
+    id: u64,
+    label: String,
+}
+
+/// What we mirrored from a collection so far: Wallhaven id → local file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    items: HashMap<String, PathBuf>,
+}
+
+/// Outcome of one [`sync_collection`] run.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub folder: PathBuf,
+    pub downloaded: usize,
+    pub present: usize,
+    pub removed: usize,
+    pub failed: Vec<(String, String)>,
+}
+
+fn collections_url(user: &str, id: Option<u64>, cfg: &Config, page: u32) -> Result<url::Url, WallhavenError> {
+    let mut url = api_url(cfg, "collections/")?;
+    let base = url.to_string();
+    url.path_segments_mut()
+        .map_err(|_| WallhavenError::InvalidUrl(base))?
+        .pop_if_empty()
+        .push(user)
+        .extend(id.map(|id| id.to_string()));
+    let api_key = cfg.wallhaven_api_key.trim();
+    if !api_key.is_empty() {
+        url.query_pairs_mut().append_pair("apikey", api_key);
+    }
+    if id.is_some() {
+        url.query_pairs_mut().append_pair("page", &page.to_string());
+    }
+    Ok(url)
+}
+
+/// Resolves `collection`, given as a label or a numeric id, among `user`'s collections.
+async fn find_collection(cfg: &Config, user: &str, collection: &str) -> Result<Collection, WallhavenError> {
+    let resp = get(cfg, collections_url(user, None, cfg, 1)?, &format!("User {user}"), api_timeout(cfg)).await?;
+
+    let found = resp
+        .json::<CollectionsResponse>()
+        .await?
+        .data
+        .into_iter()
+        .find(|c| c.label.eq_ignore_ascii_case(collection) || c.id.to_string() == collection);
+    found.ok_or_else(|| {
+        let hint = if cfg.wallhaven_api_key.trim().is_empty() {
+            " (private collections need wallhaven_api_key)"
+        } else {
+            ""
+        };
+        WallhavenError::NotFound(format!("Collection {user}/{collection}{hint}"))
+    })
+}
+
+async fn collection_items(cfg: &Config, user: &str, id: u64) -> Result<Vec<Wallpaper>, WallhavenError> {
+    let mut items = Vec::new();
+    let mut page = 1;
+    loop {
+        let url = collections_url(user, Some(id), cfg, page)?;
+        let resp = get(cfg, url, &format!("Collection page {page}"), api_timeout(cfg)).await?;
+
+        let payload: WallhavenResponse = resp.json().await?;
+        let last_page = payload.meta.map(|m| m.last_page).unwrap_or(1);
+        items.extend(payload.data);
+        if page >= last_page {
+            return Ok(items);
+        }
+        page += 1;
+    }
+}
+
+fn state_path(user: &str, collection: &str) -> PathBuf {
+    config::config_dir()
+        .join("wallhaven-sync")
+        .join(format!("{}.json", folder_name(user, collection)))
+}
+
+fn folder_name(user: &str, collection: &str) -> String {
+    let clean = |s: &str| -> String {
+        s.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect()
+    };
+    format!("wallhaven-{}-{}", clean(user), clean(collection))
+}
+
+/// Mirrors `user`'s `collection` into a folder inside the wallpaper folder. Only items not
+/// synced before (or whose file has gone) are downloaded; items we already have elsewhere are
+/// left where they are. With `prune`, files in the mirror folder whose wallpaper has left the
+/// collection are deleted.
+pub async fn sync_collection(cfg: &Config, user: &str, collection: &str, prune: bool) -> Result<SyncReport, WallhavenError> {
+    let found = find_collection(cfg, user, collection).await?;
+    let items = collection_items(cfg, user, found.id).await?;
+
+    let folder = config::save_wallpaper_path(cfg).join(folder_name(user, &found.label));
+    let state_file = state_path(user, &found.label);
+    let mut state: SyncState = config::read_json_or_default(&state_file);
+    let mut report = SyncReport {
+        folder: folder.clone(),
+        ..Default::default()
+    };
+
+    // Shared so the folders are scanned at most once, not once per item.
+    let local = LocalFiles::default();
+    for wp in &items {
+        if state.items.get(&wp.id).is_some_and(|p| p.exists()) {
+            report.present += 1;
+            continue;
+        }
+        match super::download_to(cfg, wp, &folder, &local).await {
+            Ok(path) => {
+                if path.starts_with(&folder) {
+                    report.downloaded += 1;
+                } else {
+                    report.present += 1;
+                }
+                state.items.insert(wp.id.clone(), path);
+            }
+            Err(e) => report.failed.push((wp.id.clone(), e.to_string())),
+        }
+        // Saved as we go so an interrupted run picks up where it stopped.
+        config::write_json_atomic(&state_file, &state)?;
+    }
+
+    if prune {
+        let gone: Vec<String> = state
+            .items
+            .keys()
+            .filter(|id| !items.iter().any(|wp| &wp.id == *id))
+            .cloned()
+            .collect();
+        for id in gone {
+            if let Some(path) = state.items.remove(&id)
+                && path.starts_with(&folder)
+                && fs::remove_file(&path).is_ok()
+            {
+                report.removed += 1;
+            }
+        }
+    }
+
+    config::write_json_atomic(&state_file, &state)?;
+    Ok(report)
+}
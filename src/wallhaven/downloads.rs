@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::config::Config;
+
+/// A wallpaper we downloaded, with its content hash so it can be recognised after a rename.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Download {
+    pub path: PathBuf,
+    pub hash: String,
+    pub len: u64,
+}
+
+fn registry_path() -> PathBuf {
+    crate::config::config_dir().join("wallhaven-downloads.json")
+}
+
+/// Wallhaven id → local file, for everything downloaded so far.
+fn registry() -> &'static Mutex<HashMap<String, Download>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Download>>> = OnceLock::new();
+
+    REGISTRY.get_or_init(|| Mutex::new(crate::config::read_json_or_default(&registry_path())))
+}
+
+fn save(map: &HashMap<String, Download>) -> io::Result<()> {
+    crate::config::write_json_atomic(&registry_path(), map)
+}
+
+fn failed_path() -> PathBuf {
+    crate::config::config_dir().join("wallhaven-failed.json")
+}
+
+/// Ids whose download failed in a way retrying won't fix, so random picks pass over them.
+fn failed() -> &'static Mutex<HashSet<String>> {
+    static FAILED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+    FAILED.get_or_init(|| Mutex::new(crate::config::read_json_or_default(&failed_path())))
+}
+
+pub fn has_failed(id: &str) -> bool {
+    failed().lock().is_ok_and(|set| set.contains(id))
+}
+
+pub fn record_failed(id: &str) -> io::Result<()> {
+    let Ok(mut set) = failed().lock() else {
+        return Ok(());
+    };
+    if !set.insert(id.to_string()) {
+        return Ok(());
+    }
+    crate::config::write_json_atomic(&failed_path(), &*set)
+}
+
+fn hash_file(path: &Path) -> io::Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+pub fn lookup(id: &str) -> Option<Download> {
+    registry().lock().ok()?.get(id).cloned()
+}
+
+/// The Wallhaven id a local file was downloaded as.
+pub fn id_for_path(path: &Path) -> Option<String> {
+    let map = registry().lock().ok()?;
+    map.iter().find(|(_, d)| d.path == path).map(|(id, _)| id.clone())
+}
+
+/// The local file of a download we still have at its recorded path.
+pub fn known_path(id: &str) -> Option<PathBuf> {
+    lookup(id).map(|d| d.path).filter(|p| p.exists())
+}
+
+pub fn record(id: &str, path: &Path) -> io::Result<()> {
+    let download = Download {
+        path: path.to_path_buf(),
+        hash: hash_file(path)?,
+        len: fs::metadata(path)?.len(),
+    };
+
+    let Ok(mut map) = registry().lock() else {
+        return Ok(());
+    };
+    map.insert(id.to_string(), download);
+    save(&map)
+}
+
+/// A file in `dir` named `wallhaven-<id>.<ext>`.
+fn named_in(dir: &Path, id: &str) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .find(|p| super::wallhaven_id(p).as_deref() == Some(id))
+}
+
+/// Looks up wallpapers among the configured folders. The folders are only scanned when the
+/// cheap checks fail, and then just once, so one `LocalFiles` should serve a whole run.
+#[derive(Default)]
+pub struct LocalFiles {
+    images: tokio::sync::OnceCell<Arc<Vec<PathBuf>>>,
+}
+
+impl LocalFiles {
+    /// Finds wallpaper `id`: at its recorded path, under its `wallhaven-<id>` name in `dir`,
+    /// or anywhere in the configured folders, moved or renamed (matched by size and hash) or
+    /// under its original name from before the registry existed. Updates the registry with
+    /// whatever is found.
+    pub async fn find(&self, cfg: &Config, id: &str, dir: &Path) -> Option<PathBuf> {
+        if let Some(p) = known_path(id) {
+            return Some(p);
+        }
+        if lookup(id).is_none()
+            && let Some(p) = named_in(dir, id)
+        {
+            let _ = record(id, &p);
+            return Some(p);
+        }
+
+        let images = self
+            .images
+            .get_or_init(|| async { Arc::new(crate::scanner::scan_directories(cfg.folders.clone()).await) })
+            .await
+            .clone();
+        find_in(images, id).await
+    }
+}
+
+async fn find_in(images: Arc<Vec<PathBuf>>, id: &str) -> Option<PathBuf> {
+    let known = lookup(id);
+    let id = id.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        let found = images.iter().find(|p| match &known {
+            Some(d) => {
+                fs::metadata(p).is_ok_and(|m| m.len() == d.len)
+                    && hash_file(p).is_ok_and(|h| h == d.hash)
+            }
+            None => super::wallhaven_id(p).as_deref() == Some(id.as_str()),
+        })?;
+
+        let _ = record(&id, found);
+        Some(found.clone())
+    })
+    .await
+    .ok()
+    .flatten()
+}
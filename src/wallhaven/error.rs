@@ -0,0 +1,116 @@
+use reqwest::StatusCode;
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong talking to Wallhaven, worded for showing to the user.
+#[derive(Debug)]
+pub enum WallhavenError {
+    /// The request never got a response: offline, DNS, TLS, timeout, or a garbled body.
+    Network(reqwest::Error),
+    /// The API key was rejected or is needed for what was asked.
+    Unauthorized,
+    /// Still HTTP 429 after backing off.
+    RateLimited,
+    NotFound(String),
+    /// Any other unsuccessful response, after retrying server errors.
+    Status { context: String, status: StatusCode },
+    InvalidSearch(String),
+    NoResults,
+    TooLarge { limit_mb: u64 },
+    Truncated { got: u64, expected: u64 },
+    /// No data arrived for `secs` seconds in the middle of a download.
+    Stalled { secs: u64 },
+    InvalidImage(String),
+    InvalidUrl(String),
+    /// The proxy or CA bundle settings are unusable.
+    Client(String),
+    Io(io::Error),
+}
+
+impl WallhavenError {
+    pub(super) fn from_status(status: StatusCode, context: &str) -> Self {
+        match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => WallhavenError::Unauthorized,
+            StatusCode::TOO_MANY_REQUESTS => WallhavenError::RateLimited,
+            StatusCode::NOT_FOUND => WallhavenError::NotFound(context.to_string()),
+            status => WallhavenError::Status {
+                context: context.to_string(),
+                status,
+            },
+        }
+    }
+
+    /// Whether trying the same wallpaper again is pointless: it is gone, too large or not an
+    /// image. Anything else may well work next time.
+    pub(super) fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            WallhavenError::NotFound(_) | WallhavenError::TooLarge { .. } | WallhavenError::InvalidImage(_)
+        )
+    }
+}
+
+impl fmt::Display for WallhavenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WallhavenError::Network(e) if e.is_decode() => write!(f, "Unexpected response from Wallhaven: {e}"),
+            WallhavenError::Network(e) if e.is_timeout() => write!(f, "Wallhaven did not answer in time"),
+            WallhavenError::Network(e) if e.is_connect() => {
+                write!(f, "Could not reach Wallhaven; are you offline? ({e})")
+            }
+            WallhavenError::Network(e) => write!(f, "Request to Wallhaven failed: {e}"),
+            WallhavenError::Unauthorized => {
+                write!(f, "Wallhaven refused the request; check wallhaven_api_key in the config")
+            }
+            WallhavenError::RateLimited => write!(f, "Wallhaven is rate limiting us; try again in a minute"),
+            WallhavenError::NotFound(what) => write!(f, "{what} was not found on Wallhaven"),
+            WallhavenError::Status { context, status } => write!(f, "{context} failed: HTTP {status}"),
+            WallhavenError::InvalidSearch(e) => write!(f, "Invalid Wallhaven search: {e}"),
+            WallhavenError::NoResults => write!(f, "No new Wallhaven wallpapers match the search"),
+            WallhavenError::TooLarge { limit_mb } => write!(f, "Download is over the {limit_mb} MB limit"),
+            WallhavenError::Truncated { got, expected } => {
+                write!(f, "Download was cut short: got {got} of {expected} bytes")
+            }
+            WallhavenError::Stalled { secs } => write!(f, "Download stalled: no data for {secs} s"),
+            WallhavenError::InvalidImage(e) => write!(f, "Downloaded file is not a valid image: {e}"),
+            WallhavenError::InvalidUrl(e) => write!(f, "Invalid Wallhaven URL: {e}"),
+            WallhavenError::Client(e) => write!(f, "Invalid Wallhaven HTTP settings: {e}"),
+            WallhavenError::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for WallhavenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WallhavenError::Network(e) => Some(e),
+            WallhavenError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for WallhavenError {
+    fn from(e: reqwest::Error) -> Self {
+        // The URL may carry the API key.
+        WallhavenError::Network(e.without_url())
+    }
+}
+
+impl From<io::Error> for WallhavenError {
+    fn from(e: io::Error) -> Self {
+        WallhavenError::Io(e)
+    }
+}
+
+impl From<url::ParseError> for WallhavenError {
+    fn from(e: url::ParseError) -> Self {
+        WallhavenError::InvalidUrl(e.to_string())
+    }
+}
+
+impl From<tokio::task::JoinError> for WallhavenError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        WallhavenError::Io(io::Error::other(e))
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::{Thumbs, WallhavenError, Wallpaper, api_timeout, api_url, get};
+use crate::config::{Blacklist, Config};
+
+/// Everything `/api/v1/w/<id>` tells us about a wallpaper, kept for attribution and tags.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Metadata {
+    pub id: String,
+    /// The wallpaper's page on wallhaven.cc.
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub resolution: String,
+    #[serde(default)]
+    pub file_size: u64,
+    #[serde(default)]
+    pub thumbs: Thumbs,
+    #[serde(default)]
+    pub uploader: Option<Uploader>,
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub category: String,
+    #[serde(default)]
+    pub purity: String,
+    #[serde(default)]
+    pub colors: Vec<String>,
+    /// Where the uploader got the image from, if given.
+    #[serde(default)]
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Uploader {
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Tag {
+    pub name: String,
+}
+
+impl Metadata {
+    pub fn wallpaper(&self) -> Wallpaper {
+        Wallpaper {
+            id: self.id.clone(),
+            path: self.path.clone(),
+            resolution: self.resolution.clone(),
+            file_size: self.file_size,
+            thumbs: self.thumbs.clone(),
+        }
+    }
+
+    pub fn page_url(&self, cfg: &Config) -> String {
+        if self.url.is_empty() { super::page_url(cfg, &self.id) } else { self.url.clone() }
+    }
+
+    pub fn uploader_name(&self) -> Option<&str> {
+        self.uploader.as_ref().map(|u| u.username.as_str())
+    }
+
+    pub fn tag_names(&self) -> Vec<&str> {
+        self.tags.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    pub fn is_blacklisted(&self, list: &Blacklist) -> bool {
+        list.has_id(&self.id)
+            || self.uploader_name().is_some_and(|u| list.has_uploader(u))
+            || self.tags.iter().any(|t| list.has_tag(&t.name))
+    }
+}
+
+#[derive(Deserialize)]
+struct MetadataResponse {
+    data: Metadata,
+}
+
+/// Looks up a single wallpaper by id.
+pub async fn fetch(cfg: &Config, id: &str) -> Result<Metadata, WallhavenError> {
+    let mut url = api_url(cfg, "w/")?.join(id)?;
+    let api_key = cfg.wallhaven_api_key.trim();
+    if !api_key.is_empty() {
+        url.query_pairs_mut().append_pair("apikey", api_key);
+    }
+
+    let resp = get(cfg, url, &format!("Wallpaper {id}"), api_timeout(cfg)).await?;
+    Ok(resp.json::<MetadataResponse>().await?.data)
+}
+
+fn metadata_dir() -> PathBuf {
+    crate::config::config_dir().join("wallhaven-metadata")
+}
+
+fn metadata_path(id: &str) -> PathBuf {
+    metadata_dir().join(format!("{id}.json"))
+}
+
+pub fn save(meta: &Metadata) -> io::Result<()> {
+    crate::config::write_json_atomic(&metadata_path(&meta.id), meta)
+}
+
+pub fn load(id: &str) -> Option<Metadata> {
+    crate::config::read_json(&metadata_path(id))
+}
+
+/// Fetches and stores the metadata of `id` unless we already have it. Failures are ignored:
+/// metadata is nice to have and must not fail a download.
+pub async fn ensure(cfg: &Config, id: &str) {
+    if metadata_path(id).exists() {
+        return;
+    }
+    if let Ok(meta) = fetch(cfg, id).await {
+        let _ = save(&meta);
+    }
+}
+
+/// Metadata for a local file, if it was downloaded from Wallhaven.
+pub fn for_path(path: &Path) -> Option<Metadata> {
+    let id = super::wallhaven_id(path).or_else(|| super::downloads::id_for_path(path))?;
+    load(&id)
+}
@@ -1,77 +1,445 @@
+mod collections;
+mod downloads;
+mod error;
+pub mod metadata;
+mod queue;
+mod search;
+
+pub use collections::sync_collection;
+pub use error::WallhavenError;
+
+use downloads::LocalFiles;
+pub use metadata::Metadata;
+pub use search::{SEARCH_FLAGS, SearchParams, apply_search_flag};
+
 use crate::config::{self, Config};
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 
 #[derive(serde::Deserialize)]
 struct WallhavenResponse {
-    data: Vec<WallhavenItem>,
+    data: Vec<Wallpaper>,
+    #[serde(default)]
+    meta: Option<Meta>,
 }
 
-#[derive(serde::Deserialize)]
-struct WallhavenItem {
-    path: String,
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+struct Meta {
+    #[serde(default)]
+    current_page: u32,
+    #[serde(default)]
+    last_page: u32,
+    #[serde(default)]
+    seed: Option<String>,
+}
+
+/// One search result.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Wallpaper {
+    pub id: String,
+    /// Full-size image.
+    pub path: String,
+    #[serde(default)]
+    pub resolution: String,
+    #[serde(default)]
+    pub file_size: u64,
+    #[serde(default)]
+    pub thumbs: Thumbs,
 }
 
-pub async fn fetch_wallhaven_wallpaper(cfg: &Config) -> Result<Option<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
-    let api_key = cfg.wallhaven_api_key.trim().to_string();
-    let purity = cfg.wallhaven_purity.trim().to_string();
-    let categories = cfg.wallhaven_categories.trim().to_string();
-    let resolution = cfg.wallhaven_resolution.trim().to_string();
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Thumbs {
+    #[serde(default)]
+    pub large: String,
+    #[serde(default)]
+    pub small: String,
+}
 
-    let url = format!(
-        "https://wallhaven.cc/api/v1/search?apikey={}&categories={}&purity={}&atleast={}&ratios=landscape&sorting=random",
-        urlencoding::encode(&api_key),
-        urlencoding::encode(&categories),
-        urlencoding::encode(&purity),
-        urlencoding::encode(&resolution)
-    );
+/// One page of search results. `seed` is set for random sorting and must be passed back to
+/// get further pages of the same shuffle.
+#[derive(Debug, Clone)]
+pub struct SearchPage {
+    pub items: Vec<Wallpaper>,
+    pub page: u32,
+    pub last_page: u32,
+    pub seed: Option<String>,
+}
 
-    let client = reqwest::Client::builder()
-        .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/144.0.0.0 Safari/537.36")
-        .build()?;
+/// Attempts per request when Wallhaven answers 429 or a server error.
+const MAX_ATTEMPTS: u32 = 4;
+/// Longest we wait between attempts, whatever Retry-After asks for.
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
 
-    let resp = client.get(&url).send().await?;
-    if !resp.status().is_success() {
-        return Ok(None);
+const USER_AGENT: &str = concat!("wallpicker/", env!("CARGO_PKG_VERSION"));
+
+/// The HTTP client for all Wallhaven traffic, built on first use from the proxy, CA bundle
+/// and timeout settings of `cfg`.
+fn client(cfg: &Config) -> Result<&'static reqwest::Client, WallhavenError> {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+
+    let mut builder = reqwest::Client::builder().user_agent(USER_AGENT);
+    if cfg.wallhaven_timeout_secs > 0 {
+        builder = builder.connect_timeout(Duration::from_secs(cfg.wallhaven_timeout_secs));
     }
+    let proxy = cfg.wallhaven_proxy.trim();
+    if !proxy.is_empty() {
+        let proxy = reqwest::Proxy::all(proxy).map_err(|e| WallhavenError::Client(format!("proxy {proxy}: {e}")))?;
+        builder = builder.proxy(proxy);
+    }
+    if let Some(bundle) = &cfg.wallhaven_ca_bundle {
+        let describe = |e: &dyn std::fmt::Display| WallhavenError::Client(format!("CA bundle {}: {e}", bundle.display()));
+        let pem = fs::read(bundle).map_err(|e| describe(&e))?;
+        for cert in reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| describe(&e))? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    let client = builder.build().map_err(|e| WallhavenError::Client(e.to_string()))?;
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+fn base_url(cfg: &Config) -> &str {
+    cfg.wallhaven_base_url.trim().trim_end_matches('/')
+}
+
+/// `<wallhaven_base_url>/api/v1/`.
+fn api_base(cfg: &Config) -> String {
+    format!("{}/api/v1/", base_url(cfg))
+}
+
+fn api_url(cfg: &Config, path: &str) -> Result<url::Url, WallhavenError> {
+    Ok(url::Url::parse(&format!("{}{path}", api_base(cfg)))?)
+}
+
+/// Whole-request limit for API calls, which unlike image downloads are always small.
+fn api_timeout(cfg: &Config) -> Option<Duration> {
+    (cfg.wallhaven_timeout_secs > 0).then(|| Duration::from_secs(cfg.wallhaven_timeout_secs))
+}
 
+/// GETs `url`, retrying with exponential backoff on 429 and 5xx (honouring Retry-After).
+/// Unsuccessful responses become errors described by `context`.
+async fn get(
+    cfg: &Config,
+    url: url::Url,
+    context: &str,
+    timeout: Option<Duration>,
+) -> Result<reqwest::Response, WallhavenError> {
+    let client = client(cfg)?;
+    let mut backoff = Duration::from_secs(1);
+    let mut attempt = 1;
+    loop {
+        let mut req = client.get(url.clone());
+        if let Some(timeout) = timeout {
+            req = req.timeout(timeout);
+        }
+        let resp = req.send().await?;
+        let status = resp.status();
+        if status.is_success() {
+            return Ok(resp);
+        }
+
+        let retryable = status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error();
+        if !retryable || attempt >= MAX_ATTEMPTS {
+            return Err(WallhavenError::from_status(status, context));
+        }
+
+        let wait = retry_after(&resp).unwrap_or(backoff).min(MAX_RETRY_WAIT);
+        tokio::time::sleep(wait).await;
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+/// The delay asked for by a Retry-After header, given either in seconds or as an HTTP date.
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    let value = resp.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    // A date in the past means "now".
+    Some(at.duration_since(std::time::SystemTime::now()).unwrap_or_default())
+}
+
+/// Runs the configured search for `page`, reusing `seed` from an earlier page if given.
+pub async fn search(cfg: &Config, page: u32, seed: Option<String>) -> Result<SearchPage, WallhavenError> {
+    let mut params = SearchParams::from_config(cfg);
+    if let Some(seed) = seed
+        && params.sorting == "random"
+    {
+        params.seed = seed;
+    }
+    params.validate().map_err(WallhavenError::InvalidSearch)?;
+    let url = params.url(page)?;
+
+    let resp = get(cfg, url, "Wallhaven search", api_timeout(cfg)).await?;
     let payload: WallhavenResponse = resp.json().await?;
-    let first = match payload.data.first() {
-        Some(it) => it,
-        None => return Ok(None),
+    let meta = payload.meta.unwrap_or_default();
+    Ok(SearchPage {
+        items: payload.data,
+        page: meta.current_page.max(page),
+        last_page: meta.last_page.max(1),
+        seed: meta.seed,
+    })
+}
+
+/// Gets wallpaper `id`, downloading it only if it isn't already in one of our folders.
+pub async fn fetch_by_id(cfg: &Config, id: &str) -> Result<PathBuf, WallhavenError> {
+    let local = LocalFiles::default();
+    let dest_dir = config::save_wallpaper_path(cfg);
+    if let Some(existing) = local.find(cfg, id, &dest_dir).await {
+        return Ok(existing);
+    }
+
+    let meta = metadata::fetch(cfg, id).await?;
+    let _ = metadata::save(&meta);
+    download_to(cfg, &meta.wallpaper(), &dest_dir, &local).await
+}
+
+/// Queued results tried per pick before a download failure is reported.
+const MAX_PICK_ATTEMPTS: usize = 3;
+
+pub async fn fetch_wallhaven_wallpaper(cfg: &Config) -> Result<PathBuf, WallhavenError> {
+    let mut attempt = 1;
+    let dest_path = loop {
+        let wp = queue::next_wallpaper(cfg).await?.ok_or(WallhavenError::NoResults)?;
+        match download(cfg, &wp).await {
+            Ok(path) => break path,
+            Err(e) => {
+                // The queue already moved past it; make sure a later refill does too.
+                if e.is_permanent() {
+                    let _ = downloads::record_failed(&wp.id);
+                }
+                if attempt >= MAX_PICK_ATTEMPTS {
+                    return Err(e);
+                }
+                attempt += 1;
+            }
+        }
     };
 
-    let img_url = &first.path;
+    if cfg.copy_to_tmp {
+        let _ = crate::wallpaper::copy_to_current_wallpaper(&dest_path);
+    }
 
-    let dest_dir = config::save_wallpaper_path(cfg);
-    fs::create_dir_all(&dest_dir)?;
+    Ok(dest_path)
+}
 
-    let filename = file_name_from_url(img_url).unwrap_or_else(|| "wallhaven_random.jpg".to_string());
-    let dest_path = dest_dir.join(filename);
-    let mut img_resp = client.get(img_url).send().await?;
+fn file_name(wp: &Wallpaper) -> String {
+    file_name_from_url(&wp.path).unwrap_or_else(|| format!("wallhaven-{}.jpg", wp.id))
+}
+
+/// Whether a result should not be offered again because we already have it, its id is
+/// blacklisted or it could not be downloaded before.
+fn is_skipped(cfg: &Config, wp: &Wallpaper) -> bool {
+    cfg.wallhaven_blacklist.has_id(&wp.id)
+        || downloads::has_failed(&wp.id)
+        || downloads::known_path(&wp.id).is_some()
+        || config::save_wallpaper_path(cfg).join(file_name(wp)).exists()
+}
+
+/// Whether a blacklisted tag or uploader rules out `wp`. Search results carry neither, so this
+/// looks the wallpaper up, keeping the metadata for when it's downloaded. A wallpaper that
+/// can't be looked up counts as blacklisted: better to skip one than to show a banned tag.
+async fn is_blacklisted(cfg: &Config, wp: &Wallpaper) -> bool {
+    let list = &cfg.wallhaven_blacklist;
+    if list.tags.is_empty() && list.uploaders.is_empty() {
+        return false;
+    }
+
+    let meta = match metadata::load(&wp.id) {
+        Some(meta) => meta,
+        None => match metadata::fetch(cfg, &wp.id).await {
+            Ok(meta) => meta,
+            Err(_) => return true,
+        },
+    };
+    if meta.is_blacklisted(list) {
+        return true;
+    }
+    let _ = metadata::save(&meta);
+    false
+}
+
+/// Saves `wp` into the wallpaper folder, reusing the copy fetched for previewing if there is one.
+/// If we already have it (under any name) the existing file is returned instead.
+pub async fn download(cfg: &Config, wp: &Wallpaper) -> Result<PathBuf, WallhavenError> {
+    download_to(cfg, wp, &config::save_wallpaper_path(cfg), &LocalFiles::default()).await
+}
+
+/// Like [`download`], but new files go to `dest_dir` and existing ones are looked up in `local`.
+async fn download_to(
+    cfg: &Config,
+    wp: &Wallpaper,
+    dest_dir: &Path,
+    local: &LocalFiles,
+) -> Result<PathBuf, WallhavenError> {
+    if let Some(existing) = local.find(cfg, &wp.id, dest_dir).await {
+        return Ok(existing);
+    }
+
+    let dest_path = save_new(cfg, wp, dest_dir).await?;
+    let _ = downloads::record(&wp.id, &dest_path);
+    metadata::ensure(cfg, &wp.id).await;
+    Ok(dest_path)
+}
 
-    if !img_resp.status().is_success() {
-        return Ok(None);
+async fn save_new(cfg: &Config, wp: &Wallpaper, dest_dir: &Path) -> Result<PathBuf, WallhavenError> {
+    fs::create_dir_all(dest_dir)?;
+
+    let filename = file_name(wp);
+    let dest_path = dest_dir.join(&filename);
+
+    let previewed = preview_dir().join(&filename);
+    if previewed.exists() && (fs::rename(&previewed, &dest_path).is_ok() || fs::copy(&previewed, &dest_path).is_ok()) {
+        return Ok(dest_path);
     }
 
-    let mut file = fs::File::create(&dest_path)?;
-    while let Some(chunk) = img_resp.chunk().await? {
+    fetch_to(cfg, &wp.path, &dest_path).await?;
+    Ok(dest_path)
+}
+
+/// Where full images are fetched to for previewing before the user picks one.
+pub fn preview_dir() -> PathBuf {
+    crate::cache::cache_dir().join("wallhaven-preview")
+}
+
+/// Fetches the full image of `wp` into [`preview_dir`] so it can be opened in the preview.
+pub async fn download_preview(cfg: &Config, wp: &Wallpaper) -> Result<PathBuf, WallhavenError> {
+    let dir = preview_dir();
+    fs::create_dir_all(&dir)?;
+
+    let dest_path = dir.join(file_name(wp));
+    if !dest_path.exists() {
+        fetch_to(cfg, &wp.path, &dest_path).await?;
+    }
+    Ok(dest_path)
+}
+
+/// Drops previewed images that were never chosen.
+pub fn clear_previews() {
+    let _ = fs::remove_dir_all(preview_dir());
+}
+
+/// Downloads `url` to `dest_path` without ever leaving a broken file there: the body goes to
+/// a hidden `.part` file next to it, is checked against Content-Length and the size limit,
+/// must decode as an image, and only then is renamed into place.
+async fn fetch_to(cfg: &Config, url: &str, dest_path: &Path) -> Result<(), WallhavenError> {
+    let name = dest_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let part = dest_path.with_file_name(format!(".{name}.part"));
+
+    let res = match fetch_part(cfg, url, &part).await {
+        Ok(()) => fs::rename(&part, dest_path).map_err(WallhavenError::from),
+        Err(e) => Err(e),
+    };
+    if res.is_err() {
+        let _ = fs::remove_file(&part);
+    }
+    res
+}
+
+async fn fetch_part(cfg: &Config, url: &str, part: &Path) -> Result<(), WallhavenError> {
+    let max = cfg.wallhaven_max_download_mb.saturating_mul(1024 * 1024);
+    let too_big = |n: u64| max > 0 && n > max;
+
+    let mut img_resp = get(cfg, url::Url::parse(url)?, "Image", None).await?;
+
+    let expected = img_resp.content_length();
+    if let Some(len) = expected
+        && too_big(len)
+    {
+        return Err(WallhavenError::TooLarge {
+            limit_mb: cfg.wallhaven_max_download_mb,
+        });
+    }
+
+    let mut file = fs::File::create(part)?;
+    let mut written = 0u64;
+    // No overall timeout, as large images may take a while; a connection that goes quiet
+    // for `wallhaven_timeout_secs` is given up on instead.
+    let idle = api_timeout(cfg);
+    loop {
+        let next = img_resp.chunk();
+        let chunk = match idle {
+            Some(idle) => tokio::time::timeout(idle, next)
+                .await
+                .map_err(|_| WallhavenError::Stalled { secs: idle.as_secs() })??,
+            None => next.await?,
+        };
+        let Some(chunk) = chunk else {
+            break;
+        };
+        written += chunk.len() as u64;
+        if too_big(written) {
+            return Err(WallhavenError::TooLarge {
+                limit_mb: cfg.wallhaven_max_download_mb,
+            });
+        }
         file.write_all(&chunk)?;
     }
+    file.sync_all()?;
+    drop(file);
 
-    if cfg.copy_to_tmp {
-        let _ = crate::wallpaper::copy_to_current_wallpaper(&dest_path);
+    if let Some(len) = expected
+        && len != written
+    {
+        return Err(WallhavenError::Truncated {
+            got: written,
+            expected: len,
+        });
     }
 
-    Ok(Some(dest_path))
+    let part = part.to_path_buf();
+    tokio::task::spawn_blocking(move || -> Result<(), WallhavenError> {
+        image::io::Reader::open(&part)?
+            .with_guessed_format()?
+            .decode()
+            .map_err(|e| WallhavenError::InvalidImage(e.to_string()))?;
+        Ok(())
+    })
+    .await?
+}
+
+/// Fetches a small remote image such as a search result thumbnail.
+pub async fn fetch_thumbnail(cfg: &Config, url: String) -> Option<Vec<u8>> {
+    let mut req = client(cfg).ok()?.get(url);
+    if let Some(timeout) = api_timeout(cfg) {
+        req = req.timeout(timeout);
+    }
+    let resp = req.send().await.ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    resp.bytes().await.ok().map(|b| b.to_vec())
 }
 
 fn file_name_from_url(url: &str) -> Option<String> {
     let parsed = url::Url::parse(url).ok()?;
     parsed
         .path_segments()
-        .and_then(|segs| segs.last())
+        .and_then(|mut segs| segs.next_back())
         .filter(|s| !s.is_empty())
         .map(|s| s.to_string())
 }
+
+/// The Wallhaven id of a file downloaded by us, from its `wallhaven-<id>.<ext>` name.
+pub fn wallhaven_id(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    stem.strip_prefix("wallhaven-")
+        .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric()))
+        .map(|id| id.to_string())
+}
+
+/// The wallpaper's page on the site, under the configured base URL.
+pub fn page_url(cfg: &Config, id: &str) -> String {
+    format!("{}/w/{id}", base_url(cfg))
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io;
+use std::path::PathBuf;
+
+use super::{WallhavenError, SearchParams, Wallpaper, search};
+use crate::config::Config;
+
+/// Search pages fetched per request at most, so a search where everything is already
+/// downloaded gives up instead of walking every page.
+const MAX_REFILLS: usize = 3;
+
+/// Results of the configured search that haven't been used yet, persisted so `--random`,
+/// the tray and the picker draw from one page of results instead of searching every time.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Queue {
+    /// The search these results belong to; a config change starts a new queue.
+    search: String,
+    seed: Option<String>,
+    next_page: u32,
+    last_page: u32,
+    items: VecDeque<Wallpaper>,
+}
+
+fn queue_path() -> PathBuf {
+    crate::cache::cache_dir().join("wallhaven-queue.json")
+}
+
+fn load() -> Queue {
+    crate::config::read_json_or_default(&queue_path())
+}
+
+fn save(q: &Queue) -> io::Result<()> {
+    crate::config::write_json_atomic(&queue_path(), q)
+}
+
+/// Identifies a search independently of paging, the random seed and the API key.
+fn search_key(params: &SearchParams) -> String {
+    let mut p = params.clone();
+    p.seed.clear();
+    p.api_key.clear();
+    p.url(1).map(|u| u.to_string()).unwrap_or_default()
+}
+
+fn lock() -> &'static tokio::sync::Mutex<()> {
+    static LOCK: std::sync::OnceLock<tokio::sync::Mutex<()>> = std::sync::OnceLock::new();
+    LOCK.get_or_init(|| tokio::sync::Mutex::new(()))
+}
+
+/// The next queued result that we don't have yet, refilling the queue a page at a time.
+/// Once the last page is used up the search starts over (with a new shuffle for random sorting).
+pub async fn next_wallpaper(cfg: &Config) -> Result<Option<Wallpaper>, WallhavenError> {
+    let _guard = lock().lock().await;
+
+    let params = SearchParams::from_config(cfg);
+    params.validate().map_err(WallhavenError::InvalidSearch)?;
+    let key = search_key(&params);
+
+    let mut q = load();
+    if q.search != key {
+        q = Queue {
+            search: key,
+            next_page: 1,
+            ..Default::default()
+        };
+    }
+
+    let mut refills = 0;
+    loop {
+        while let Some(wp) = q.items.pop_front() {
+            if super::is_skipped(cfg, &wp) || super::is_blacklisted(cfg, &wp).await {
+                continue;
+            }
+            let _ = save(&q);
+            return Ok(Some(wp));
+        }
+
+        if refills == MAX_REFILLS {
+            let _ = save(&q);
+            return Ok(None);
+        }
+        if q.last_page > 0 && q.next_page > q.last_page {
+            q.next_page = 1;
+            q.seed = None;
+        }
+
+        let page = search(cfg, q.next_page, q.seed.clone()).await?;
+        refills += 1;
+
+        if q.seed.is_none() {
+            q.seed = page.seed.clone();
+        }
+        q.last_page = page.last_page;
+        q.next_page = page.page + 1;
+        if page.items.is_empty() {
+            let _ = save(&q);
+            return Ok(None);
+        }
+        q.items.extend(page.items);
+    }
+}
@@ -0,0 +1,212 @@
+use crate::config::Config;
+
+const SORTINGS: &[&str] = &["date_added", "relevance", "random", "views", "favorites", "toplist"];
+const ORDERS: &[&str] = &["desc", "asc"];
+const TOP_RANGES: &[&str] = &["1d", "3d", "1w", "1M", "3M", "6M", "1y"];
+/// The only colours the API accepts for `colors`.
+const PALETTE: &[&str] = &[
+    "660000", "990000", "cc0000", "cc3333", "ea4c88", "993399", "663399", "333399", "0066cc",
+    "0099cc", "66cccc", "77cc33", "669900", "336600", "666600", "999900", "cccc33", "ffff00",
+    "ffcc33", "ff9900", "ff6600", "cc6633", "996633", "663300", "000000", "999999", "cccccc",
+    "ffffff", "424153",
+];
+
+/// Command-line flags accepted with `--random`, each overriding the matching `wallhaven_*`
+/// setting for that run.
+pub const SEARCH_FLAGS: &[&str] = &[
+    "--query",
+    "--categories",
+    "--purity",
+    "--atleast",
+    "--resolutions",
+    "--ratios",
+    "--colors",
+    "--sorting",
+    "--order",
+    "--top-range",
+    "--seed",
+];
+
+/// Overrides the config setting behind one of [`SEARCH_FLAGS`]. Any other flag is an error.
+pub fn apply_search_flag(cfg: &mut Config, flag: &str, value: String) -> Result<(), String> {
+    let field = match flag {
+        "--query" => &mut cfg.wallhaven_query,
+        "--categories" => &mut cfg.wallhaven_categories,
+        "--purity" => &mut cfg.wallhaven_purity,
+        "--atleast" => &mut cfg.wallhaven_resolution,
+        "--resolutions" => &mut cfg.wallhaven_resolutions,
+        "--ratios" => &mut cfg.wallhaven_ratios,
+        "--colors" => &mut cfg.wallhaven_colors,
+        "--sorting" => &mut cfg.wallhaven_sorting,
+        "--order" => &mut cfg.wallhaven_order,
+        "--top-range" => &mut cfg.wallhaven_top_range,
+        "--seed" => &mut cfg.wallhaven_seed,
+        _ => return Err(format!("{flag} is not a Wallhaven search flag")),
+    };
+    *field = value;
+    Ok(())
+}
+
+/// A Wallhaven search as configured. Empty fields are left out of the request so the API's
+/// own defaults apply.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchParams {
+    /// The search endpoint, under the configured base URL.
+    pub endpoint: String,
+    pub api_key: String,
+    pub query: String,
+    pub categories: String,
+    pub purity: String,
+    pub atleast: String,
+    pub resolutions: String,
+    pub ratios: String,
+    pub colors: String,
+    pub sorting: String,
+    pub order: String,
+    pub top_range: String,
+    pub seed: String,
+}
+
+impl SearchParams {
+    pub fn from_config(cfg: &Config) -> Self {
+        Self {
+            endpoint: format!("{}search", super::api_base(cfg)),
+            api_key: cfg.wallhaven_api_key.trim().to_string(),
+            query: exclude_tags(cfg.wallhaven_query.trim(), &cfg.wallhaven_blacklist.tags),
+            categories: cfg.wallhaven_categories.trim().to_string(),
+            purity: cfg.wallhaven_purity.trim().to_string(),
+            atleast: cfg.wallhaven_resolution.trim().to_string(),
+            resolutions: cfg.wallhaven_resolutions.trim().to_string(),
+            ratios: cfg.wallhaven_ratios.trim().to_string(),
+            colors: cfg.wallhaven_colors.trim().trim_start_matches('#').to_lowercase(),
+            sorting: cfg.wallhaven_sorting.trim().to_string(),
+            order: cfg.wallhaven_order.trim().to_string(),
+            top_range: cfg.wallhaven_top_range.trim().to_string(),
+            seed: cfg.wallhaven_seed.trim().to_string(),
+        }
+    }
+
+    /// Checks every value before anything is sent, so mistakes show up as a clear message
+    /// instead of an empty or rejected search.
+    pub fn validate(&self) -> Result<(), String> {
+        check_flags("categories", &self.categories, "general/anime/people")?;
+        check_flags("purity", &self.purity, "sfw/sketchy/nsfw")?;
+        if self.purity.chars().nth(2) == Some('1') && self.api_key.is_empty() {
+            return Err(format!(
+                "purity \"{}\" includes NSFW, which needs wallhaven_api_key to be set",
+                self.purity
+            ));
+        }
+
+        if !self.atleast.is_empty() && !is_resolution(&self.atleast) {
+            return Err(format!("atleast \"{}\" is not a resolution like 1920x1080", self.atleast));
+        }
+        for r in split_list(&self.resolutions) {
+            if !is_resolution(r) {
+                return Err(format!("resolutions: \"{r}\" is not a resolution like 1920x1080"));
+            }
+        }
+        for r in split_list(&self.ratios) {
+            if r != "landscape" && r != "portrait" && !is_resolution(r) {
+                return Err(format!(
+                    "ratios: \"{r}\" is not landscape, portrait or a ratio like 16x9"
+                ));
+            }
+        }
+
+        if !self.colors.is_empty() && !PALETTE.contains(&self.colors.as_str()) {
+            return Err(format!(
+                "colors \"{}\" is not in Wallhaven's palette ({})",
+                self.colors,
+                PALETTE.join(", ")
+            ));
+        }
+
+        check_choice("sorting", &self.sorting, SORTINGS)?;
+        check_choice("order", &self.order, ORDERS)?;
+        check_choice("top_range", &self.top_range, TOP_RANGES)?;
+        if !self.top_range.is_empty() && self.sorting != "toplist" {
+            return Err(String::from("top_range only applies when sorting is toplist"));
+        }
+
+        if !self.seed.is_empty() {
+            if self.seed.len() != 6 || !self.seed.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(format!("seed \"{}\" must be 6 letters or digits", self.seed));
+            }
+            if self.sorting != "random" {
+                return Err(String::from("seed only applies when sorting is random"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The search URL for `page` (1-based).
+    pub fn url(&self, page: u32) -> Result<url::Url, url::ParseError> {
+        let page = page.to_string();
+        let pairs = [
+            ("apikey", self.api_key.as_str()),
+            ("q", self.query.as_str()),
+            ("categories", self.categories.as_str()),
+            ("purity", self.purity.as_str()),
+            ("atleast", self.atleast.as_str()),
+            ("resolutions", self.resolutions.as_str()),
+            ("ratios", self.ratios.as_str()),
+            ("colors", self.colors.as_str()),
+            ("sorting", self.sorting.as_str()),
+            ("order", self.order.as_str()),
+            ("topRange", self.top_range.as_str()),
+            ("seed", self.seed.as_str()),
+            ("page", if page == "1" { "" } else { page.as_str() }),
+        ];
+
+        url::Url::parse_with_params(&self.endpoint, pairs.iter().filter(|(_, v)| !v.is_empty()))
+    }
+}
+
+/// Adds `-tag` to `query` for each blacklisted tag. Tags with spaces can't be excluded in the
+/// query and are only filtered out after looking each result up.
+fn exclude_tags(query: &str, tags: &[String]) -> String {
+    let excluded = tags
+        .iter()
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty() && !t.contains(char::is_whitespace))
+        .map(|t| format!("-{t}"));
+    std::iter::once(query.to_string())
+        .chain(excluded)
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn check_flags(name: &str, value: &str, meaning: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return Ok(());
+    }
+    if value.len() != 3 || !value.chars().all(|c| c == '0' || c == '1') {
+        return Err(format!("{name} \"{value}\" must be three 0/1 digits ({meaning})"));
+    }
+    if value == "000" {
+        return Err(format!("{name} \"000\" would match nothing"));
+    }
+    Ok(())
+}
+
+fn check_choice(name: &str, value: &str, allowed: &[&str]) -> Result<(), String> {
+    if value.is_empty() || allowed.contains(&value) {
+        Ok(())
+    } else {
+        Err(format!("{name} \"{value}\" must be one of {}", allowed.join(", ")))
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+fn is_resolution(value: &str) -> bool {
+    match value.split_once('x') {
+        Some((w, h)) => w.parse::<u32>().is_ok_and(|w| w > 0) && h.parse::<u32>().is_ok_and(|h| h > 0),
+        None => false,
+    }
+}
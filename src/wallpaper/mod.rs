@@ -1,6 +1,6 @@
 use std::fs;
 use std::path::{Path, PathBuf};
-use crate::config::Config;
+use crate::config::{Config, FillMode, Placement};
 use crate::scanner::scan_directories;
 use rand::seq::SliceRandom;
 
@@ -12,6 +12,44 @@ pub fn copy_to_current_wallpaper<P: AsRef<Path>>(path: P) -> std::io::Result<()>
     Ok(())
 }
 
+/// The file to hand to the backend for `path`. Backends fill the screen by default, so that
+/// mode uses the original; other modes use a cached screen-sized render, falling back to the
+/// original if rendering fails.
+pub fn prepare_for_screen(path: &Path, placement: Placement) -> PathBuf {
+    if placement.mode == FillMode::Fill {
+        return path.to_path_buf();
+    }
+
+    let screen = crate::image::screen_resolution();
+    let [r, g, b] = placement.background;
+    let tag = format!(
+        "{}|{}x{}|{:02x}{:02x}{:02x}",
+        placement.mode.label(),
+        screen.0,
+        screen.1,
+        r,
+        g,
+        b
+    );
+    let target = crate::cache::composite_path(path, &tag);
+    if target.exists() {
+        return target;
+    }
+
+    let rendered = image::open(path)
+        .map(|img| crate::image::render_for_screen(&img, screen, placement))
+        .map_err(std::io::Error::other)
+        .and_then(|canvas| crate::cache::write_composite(&target, &canvas));
+
+    match rendered {
+        Ok(()) => target,
+        Err(e) => {
+            eprintln!("Failed to render {} for the screen: {e}", path.display());
+            path.to_path_buf()
+        }
+    }
+}
+
 pub async fn find_random_wallpaper(cfg: &Config) -> Result<Option<PathBuf>, Box<dyn std::error::Error + Send + Sync>> {
     let wallpapers = scan_directories(cfg.folders.clone()).await;
 